@@ -1,4 +1,4 @@
-use arqoii::types::QoiChannels;
+use arqoii::types::{QoiChannels, QoiHeader};
 use eframe::{
     egui::{
         self,
@@ -14,11 +14,14 @@ struct ArqoiiViewer {
 
 struct QoiLoader {
     cache: RwLock<HashMap<String, Arc<ColorImage>>>,
+    /// the maximum width and height of an image, the limit of the graphics backend if `None`
+    max_texture_size: Option<usize>,
 }
 impl QoiLoader {
-    fn new() -> Self {
+    fn new(max_texture_size: Option<usize>) -> Self {
         Self {
             cache: Default::default(),
+            max_texture_size,
         }
     }
 }
@@ -30,7 +33,7 @@ impl ImageLoader for QoiLoader {
 
     fn load(
         &self,
-        _ctx: &eframe::egui::Context,
+        ctx: &eframe::egui::Context,
         uri: &str,
         _size_hint: eframe::egui::SizeHint,
     ) -> eframe::egui::load::ImageLoadResult {
@@ -52,8 +55,23 @@ impl ImageLoader for QoiLoader {
                 }
                 std::collections::hash_map::Entry::Vacant(placeholder) => {
                     println!("Cache Miss for {uri}");
-                    let data =
-                        std::fs::read(uri).map_err(|err| LoadError::Loading(err.to_string()))?;
+                    let data = super::qoi::read(uri.as_ref())
+                        .map_err(|err| LoadError::Loading(err.to_string()))?;
+
+                    let header = QoiHeader::try_from(&data[..])
+                        .map_err(|err| LoadError::Loading(err.to_string()))?;
+                    let max_texture_size = self
+                        .max_texture_size
+                        .unwrap_or_else(|| ctx.input(|i| i.max_texture_side));
+                    // egui panics when creating a texture exceeding the limit
+                    if header.width as usize > max_texture_size
+                        || header.height as usize > max_texture_size
+                    {
+                        return Err(LoadError::Loading(format!(
+                            "{}x{} exceeds the maximum texture size of {max_texture_size}x{max_texture_size}",
+                            header.width, header.height
+                        )));
+                    }
 
                     let (header, pixel) = super::qoi::load(&data);
                     let size = [header.width as usize, header.height as usize];
@@ -66,13 +84,9 @@ impl ImageLoader for QoiLoader {
                                 .flat_map(|px| [px.r, px.g, px.b])
                                 .collect::<Vec<_>>(),
                         ),
-                        QoiChannels::Rgba => ColorImage::from_rgba_unmultiplied(
-                            size,
-                            &pixel
-                                .into_iter()
-                                .flat_map(|px| [px.r, px.g, px.b, px.a])
-                                .collect::<Vec<_>>(),
-                        ),
+                        QoiChannels::Rgba => {
+                            ColorImage::from_rgba_unmultiplied(size, bytemuck::cast_slice(&pixel))
+                        }
                     };
 
                     println!("Loaded {uri}");
@@ -104,8 +118,13 @@ impl ImageLoader for QoiLoader {
 }
 
 impl ArqoiiViewer {
-    fn new(ctx: &eframe::CreationContext, image_paths: Vec<PathBuf>) -> Self {
-        ctx.egui_ctx.add_image_loader(Arc::new(QoiLoader::new()));
+    fn new(
+        ctx: &eframe::CreationContext,
+        image_paths: Vec<PathBuf>,
+        max_texture_size: Option<usize>,
+    ) -> Self {
+        ctx.egui_ctx
+            .add_image_loader(Arc::new(QoiLoader::new(max_texture_size)));
         Self { image_paths }
     }
 }
@@ -142,12 +161,12 @@ impl eframe::App for ArqoiiViewer {
     }
 }
 
-pub(crate) fn open(args: crate::CmdArgs) {
+pub(crate) fn open(image_paths: Vec<PathBuf>, max_texture_size: Option<usize>) {
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "Arqoii Viewer",
         native_options,
-        Box::new(|cc| Box::new(ArqoiiViewer::new(cc, args.paths))),
+        Box::new(move |cc| Box::new(ArqoiiViewer::new(cc, image_paths, max_texture_size))),
     )
     .unwrap();
 }
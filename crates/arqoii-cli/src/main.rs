@@ -1,5 +1,6 @@
 use std::path::{Path, PathBuf};
 
+use arqoii::types::{Pixel, QoiChannels};
 use clap::{Parser, Subcommand};
 
 mod gui;
@@ -17,17 +18,43 @@ struct CmdArgs {
 
 #[derive(Subcommand, Debug)]
 enum Command {
-    View,
-    Convert,
+    View {
+        /// Refuse to show images wider or higher than this, defaults to the limit of the graphics backend
+        #[arg(long)]
+        max_texture_size: Option<usize>,
+    },
+    Convert {
+        /// Skip frames of an animated png that are identical to the previous frame
+        #[arg(long)]
+        dedupe: bool,
+        /// The filter to use when writing png files
+        #[arg(long, value_enum, default_value_t)]
+        png_filter: png::PngFilter,
+        /// Decode the written qoi files again and check that they contain the source pixels
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Print the header and a listing of the chunks of qoi files
+    Inspect {
+        /// Only list the first N chunks of each file
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Compare two qoi files, showing the chunks around the first difference
+    Diff,
 }
 
 fn main() {
     let args = CmdArgs::parse();
     match args.command {
-        Command::View => {
-            gui::open(args);
+        Command::View { max_texture_size } => {
+            gui::open(args.paths, max_texture_size);
         }
-        Command::Convert => {
+        Command::Convert {
+            dedupe,
+            png_filter,
+            verify,
+        } => {
             for src in args.paths {
                 let ext = src.extension();
 
@@ -40,40 +67,101 @@ fn main() {
                 };
 
                 if ext == "png" {
-                    transcode_png_to_qoi(&src);
+                    transcode_png_to_qoi(&src, dedupe, verify);
                 } else if ext == "qoi" {
-                    transcode_qoi_to_png(&src);
+                    transcode_qoi_to_png(&src, png_filter);
                 }
             }
         }
+        Command::Inspect { limit } => {
+            for src in args.paths {
+                let qoi_bytes = qoi::read(&src).unwrap();
+                println!("{}:", src.display());
+                qoi::inspect(&qoi_bytes, limit);
+            }
+        }
+        Command::Diff => {
+            let [a, b] = args.paths.as_slice() else {
+                eprintln!("Diff expects exactly two files");
+                std::process::exit(2);
+            };
+            let a_bytes = qoi::read(a).unwrap();
+            let b_bytes = qoi::read(b).unwrap();
+            qoi::diff((a, &a_bytes), (b, &b_bytes));
+        }
     }
 }
 
-fn transcode_png_to_qoi(src: &Path) {
+fn transcode_png_to_qoi(src: &Path, dedupe: bool, verify: bool) {
     let png_bytes = std::fs::read(src).unwrap();
-    let (channels, size, frames) = png::load(&png_bytes);
+    let (channels, size, frames) = match png::load(&png_bytes) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("Skipping {}, as {err}!", src.display());
+            return;
+        }
+    };
     match frames.as_slice() {
         [frame] => {
             let dest = src.with_extension("qoi");
             qoi::save(channels, size, frame, &dest);
+            if verify {
+                verify_qoi(&dest, channels, frame);
+            }
         }
         _ => {
             for (idx, frame) in frames.iter().enumerate() {
+                if dedupe && idx > 0 && frames[idx - 1] == *frame {
+                    println!(
+                        "Skipping frame {idx} of {}, as it is identical to the previous frame",
+                        src.display()
+                    );
+                    continue;
+                }
                 let dest = src.with_extension(format!("{idx}.qoi"));
-                qoi::save(channels.clone(), size, frame, &dest);
+                qoi::save(channels, size, frame, &dest);
+                if verify {
+                    verify_qoi(&dest, channels, frame);
+                }
             }
         }
     }
 }
 
-fn transcode_qoi_to_png(src: &Path) {
+/// Check that the qoi file contains the expected pixels, exiting with an error otherwise
+///
+/// For rgb images the alpha channel is ignored
+fn verify_qoi(dest: &Path, channels: QoiChannels, expected: &[Pixel]) {
+    let qoi_bytes = qoi::read(dest).unwrap();
+    let (_header, pixels) = qoi::load(&qoi_bytes);
+
+    let matches = pixels.len() == expected.len()
+        && pixels
+            .iter()
+            .zip(expected)
+            .all(|(px, expected)| match channels {
+                QoiChannels::Rgb => px.eq_rgb(expected),
+                QoiChannels::Rgba => px == expected,
+            });
+
+    if !matches {
+        eprintln!(
+            "Verification of {} failed, the decoded pixels differ from the source!",
+            dest.display()
+        );
+        std::process::exit(1);
+    }
+}
+
+fn transcode_qoi_to_png(src: &Path, filter: png::PngFilter) {
     let dest = src.with_extension("png");
-    let qoi_bytes = std::fs::read(src).unwrap();
+    let qoi_bytes = qoi::read(src).unwrap();
     let (header, pixels) = qoi::load(&qoi_bytes);
     png::save(
         header.channels,
         (header.width, header.height),
         &pixels,
         &dest,
+        filter,
     );
 }
@@ -4,11 +4,16 @@ use std::{
 };
 
 use arqoii::types::{Pixel, QoiChannels};
-use png::Transformations;
+use clap::ValueEnum;
+use png::{BlendOp, DisposeOp, FrameControl, OutputInfo, Transformations};
 
-pub fn load(data: &[u8]) -> (QoiChannels, (u32, u32), Vec<Vec<Pixel>>) {
-    let mut channels = QoiChannels::Rgb;
+/// The channels needed to represent all frames, the size and the frames of a png
+pub type LoadedPng = (QoiChannels, (u32, u32), Vec<Vec<Pixel>>);
 
+/// Load the frames of a png, rendering the frames of an APNG onto the canvas
+///
+/// Fails if a frame does not fit into the canvas
+pub fn load(data: &[u8]) -> Result<LoadedPng, String> {
     // The decoder is a build for reader and can be used to set various decoding options
     // via `Transformations`. The default output transformation is `Transformations::IDENTITY`.
     let mut decoder = png::Decoder::new(data);
@@ -26,80 +31,216 @@ pub fn load(data: &[u8]) -> (QoiChannels, (u32, u32), Vec<Vec<Pixel>>) {
     let (width, height) = reader.info().size();
     let pixel_count = width as usize * height as usize;
 
+    // APNG frames are rendered onto a canvas, which starts out as fully transparent black
+    let mut canvas = vec![Pixel::ZERO; pixel_count];
+
     // Read the next frame. An APNG might contain multiple frames.
     while let Ok(info) = reader.next_frame(&mut buf) {
-        let mut frame = Vec::with_capacity(pixel_count);
-
         // Grab the bytes of the image.
         let bytes = &buf[..info.buffer_size()];
-        match info.color_type {
-            png::ColorType::Grayscale => {
-                for px in bytes {
-                    // TODO grayscale to rgb isn't 1:1:1
+        let frame = frame_pixels(&info, bytes);
+
+        // frames without frame control, i.e. a plain png, cover the whole image
+        let control = reader
+            .info()
+            .frame_control()
+            .copied()
+            .unwrap_or(FrameControl {
+                width,
+                height,
+                ..FrameControl::default()
+            });
+
+        // a malformed APNG may declare frames outside of the canvas, which can't be indexed
+        if control.x_offset as u64 + control.width as u64 > width as u64
+            || control.y_offset as u64 + control.height as u64 > height as u64
+        {
+            return Err(format!(
+                "frame {} at ({}, {}) of size {}x{} exceeds the {width}x{height} canvas",
+                frames.len(),
+                control.x_offset,
+                control.y_offset,
+                control.width,
+                control.height
+            ));
+        }
+
+        let region = |canvas: &[Pixel]| {
+            (0..control.height as usize)
+                .flat_map(|y| {
+                    let start = (control.y_offset as usize + y) * width as usize
+                        + control.x_offset as usize;
+                    canvas[start..start + control.width as usize]
+                        .iter()
+                        .cloned()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let previous = (control.dispose_op == DisposeOp::Previous).then(|| region(&canvas));
+
+        for_each_in_region(&mut canvas, width, &control, frame, |dest, src| {
+            *dest = match control.blend_op {
+                BlendOp::Source => src,
+                BlendOp::Over => blend_over(src, dest),
+            }
+        });
+
+        frames.push(canvas.clone());
+
+        match control.dispose_op {
+            DisposeOp::None => {}
+            DisposeOp::Background => {
+                for_each_in_region(
+                    &mut canvas,
+                    width,
+                    &control,
+                    std::iter::repeat(Pixel::ZERO),
+                    |dest, src| *dest = src,
+                );
+            }
+            DisposeOp::Previous => {
+                for_each_in_region(
+                    &mut canvas,
+                    width,
+                    &control,
+                    previous.into_iter().flatten(),
+                    |dest, src| *dest = src,
+                );
+            }
+        }
+    }
+
+    let channels = if frames.iter().flatten().all(|px| px.a == 255) {
+        QoiChannels::Rgb
+    } else {
+        QoiChannels::Rgba
+    };
+
+    Ok((channels, (width, height), frames))
+}
+
+/// Convert the bytes of a single (sub-)frame into pixels
+fn frame_pixels(info: &OutputInfo, bytes: &[u8]) -> Vec<Pixel> {
+    let mut frame = Vec::with_capacity(info.width as usize * info.height as usize);
+
+    match info.color_type {
+        png::ColorType::Grayscale => {
+            for px in bytes {
+                // a gray value maps to the same value in all channels,
+                // this is the inverse of `Pixel::to_grayscale`
+                frame.push(Pixel {
+                    r: *px,
+                    b: *px,
+                    g: *px,
+                    a: 255,
+                });
+            }
+        }
+        png::ColorType::Rgb => {
+            for px in bytes.chunks(3) {
+                if let [r, g, b] = px {
                     frame.push(Pixel {
-                        r: *px,
-                        b: *px,
-                        g: *px,
+                        r: *r,
+                        b: *b,
+                        g: *g,
                         a: 255,
                     });
+                } else {
+                    panic!("image data of an rgb png was not a multiple of 3 bytes")
                 }
             }
-            png::ColorType::Rgb => {
-                for px in bytes.chunks(3) {
-                    if let [r, g, b] = px {
-                        frame.push(Pixel {
-                            r: *r,
-                            b: *b,
-                            g: *g,
-                            a: 255,
-                        });
-                    } else {
-                        panic!("image data of an rgb png was not a multiple of 3 bytes")
-                    }
+        }
+        png::ColorType::Indexed => {
+            unreachable!("image should have been expanded")
+        }
+        png::ColorType::GrayscaleAlpha => {
+            for px in bytes.chunks(2) {
+                if let [c, a] = px {
+                    frame.push(Pixel {
+                        r: *c,
+                        b: *c,
+                        g: *c,
+                        a: *a,
+                    });
+                } else {
+                    panic!("image data of an grayscale alpha png was not a multiple of 2 bytes")
                 }
             }
-            png::ColorType::Indexed => {
-                unreachable!("image should have been expanded")
-            }
-            png::ColorType::GrayscaleAlpha => {
-                for px in bytes.chunks(2) {
-                    if let [c, a] = px {
-                        frame.push(Pixel {
-                            r: *c,
-                            b: *c,
-                            g: *c,
-                            a: *a,
-                        });
-                        if *a != 255 {
-                            channels = QoiChannels::Rgba
-                        }
-                    } else {
-                        panic!("image data of an grayscale alpha png was not a multiple of 2 bytes")
-                    }
+        }
+        png::ColorType::Rgba => {
+            for px in bytes.chunks(4) {
+                if let [r, g, b, a] = px {
+                    frame.push(Pixel {
+                        r: *r,
+                        b: *b,
+                        g: *g,
+                        a: *a,
+                    });
+                } else {
+                    panic!("image data of an rgba png was not a multiple of 4 bytes")
                 }
             }
-            png::ColorType::Rgba => {
-                for px in bytes.chunks(4) {
-                    if let [r, g, b, a] = px {
-                        frame.push(Pixel {
-                            r: *r,
-                            b: *b,
-                            g: *g,
-                            a: *a,
-                        });
-                        if *a != 255 {
-                            channels = QoiChannels::Rgba
-                        }
-                    } else {
-                        panic!("image data of an rgba png was not a multiple of 4 bytes")
-                    }
-                }
+        }
+    }
+
+    frame
+}
+
+/// Zip the pixels of the frame region of the canvas with the pixels of the frame
+fn for_each_in_region(
+    canvas: &mut [Pixel],
+    width: u32,
+    control: &FrameControl,
+    frame: impl IntoIterator<Item = Pixel>,
+    mut f: impl FnMut(&mut Pixel, Pixel),
+) {
+    let rows = canvas
+        .chunks_exact_mut(width as usize)
+        .skip(control.y_offset as usize)
+        .take(control.height as usize);
+    let region = rows.flat_map(|row| {
+        row.iter_mut()
+            .skip(control.x_offset as usize)
+            .take(control.width as usize)
+    });
+
+    for (dest, src) in region.zip(frame) {
+        f(dest, src)
+    }
+}
+
+/// Composite `src` over `dest` using un-premultiplied alpha as described by `APNG_BLEND_OP_OVER`
+fn blend_over(src: Pixel, dest: &Pixel) -> Pixel {
+    match src.a {
+        255 => src,
+        0 => *dest,
+        _ => {
+            let src_a = src.a as u32;
+            let dest_a = dest.a as u32 * (255 - src_a) / 255;
+            let out_a = src_a + dest_a;
+            let channel = |s: u8, d: u8| ((s as u32 * src_a + d as u32 * dest_a) / out_a) as u8;
+            Pixel {
+                r: channel(src.r, dest.r),
+                g: channel(src.g, dest.g),
+                b: channel(src.b, dest.b),
+                a: out_a as u8,
             }
         }
-        frames.push(frame);
     }
+}
 
-    (channels, (width, height), frames)
+/// The filter used when writing png files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PngFilter {
+    /// Choose the filter per row, usually the smallest output
+    #[default]
+    Adaptive,
+    None,
+    Sub,
+    Up,
+    Avg,
+    Paeth,
 }
 
 pub(crate) fn save(
@@ -107,6 +248,7 @@ pub(crate) fn save(
     (width, height): (u32, u32),
     pixels: &[Pixel],
     dest: &Path,
+    filter: PngFilter,
 ) {
     let mut file = std::fs::File::create(dest).unwrap();
     let mut buf_writer = BufWriter::new(&mut file);
@@ -116,7 +258,14 @@ pub(crate) fn save(
         QoiChannels::Rgb => png::ColorType::Rgb,
         QoiChannels::Rgba => png::ColorType::Rgba,
     });
-    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+    match filter {
+        PngFilter::Adaptive => encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive),
+        PngFilter::None => encoder.set_filter(png::FilterType::NoFilter),
+        PngFilter::Sub => encoder.set_filter(png::FilterType::Sub),
+        PngFilter::Up => encoder.set_filter(png::FilterType::Up),
+        PngFilter::Avg => encoder.set_filter(png::FilterType::Avg),
+        PngFilter::Paeth => encoder.set_filter(png::FilterType::Paeth),
+    }
     let mut writer = encoder.write_header().unwrap();
 
     let data = pixels
@@ -134,3 +283,77 @@ pub(crate) fn save(
     file.flush().unwrap();
     file.sync_data().unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use arqoii::types::{Pixel, QoiChannels};
+    use png::{BlendOp, DisposeOp};
+
+    fn rgba(pixels: &[Pixel]) -> Vec<u8> {
+        pixels
+            .iter()
+            .flat_map(|px| [px.r, px.g, px.b, px.a])
+            .collect()
+    }
+
+    #[test]
+    fn apng_frame_offsets() {
+        let red = Pixel::rgba(255, 0, 0, 255);
+        let blue = Pixel::rgba(0, 0, 255, 255);
+        let clear = Pixel::rgba(0, 255, 0, 0);
+
+        let mut data = vec![];
+        let mut encoder = png::Encoder::new(&mut data, 4, 4);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_animated(4, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+
+        // full red background
+        writer.write_image_data(&rgba(&[red; 16])).unwrap();
+
+        // blue 2x2 square at (1, 2), disposed to the previous state afterwards
+        writer.set_frame_dimension(2, 2).unwrap();
+        writer.set_frame_position(1, 2).unwrap();
+        writer.set_dispose_op(DisposeOp::Previous).unwrap();
+        writer.write_image_data(&rgba(&[blue; 4])).unwrap();
+
+        // transparent 2x1 at (2, 0) blended over the canvas, cleared afterwards
+        writer.set_frame_dimension(2, 1).unwrap();
+        writer.set_frame_position(2, 0).unwrap();
+        writer.set_blend_op(BlendOp::Over).unwrap();
+        writer.set_dispose_op(DisposeOp::Background).unwrap();
+        writer.write_image_data(&rgba(&[clear; 2])).unwrap();
+
+        // transparent 1x1 at (0, 0) replacing the canvas, showing the effect of the previous dispose
+        writer.set_frame_dimension(1, 1).unwrap();
+        writer.set_frame_position(0, 0).unwrap();
+        writer.set_blend_op(BlendOp::Source).unwrap();
+        writer.set_dispose_op(DisposeOp::None).unwrap();
+        writer
+            .write_image_data(&rgba(std::slice::from_ref(&clear)))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let (channels, size, frames) = super::load(&data).unwrap();
+
+        assert_eq!(size, (4, 4));
+        assert_eq!(channels, QoiChannels::Rgba);
+        assert_eq!(frames.len(), 4);
+
+        assert_eq!(frames[0], vec![red; 16]);
+
+        let mut expected = vec![red; 16];
+        for idx in [9, 10, 13, 14] {
+            expected[idx] = blue;
+        }
+        assert_eq!(frames[1], expected);
+
+        assert_eq!(frames[2], vec![red; 16]);
+
+        let mut expected = vec![red; 16];
+        expected[0] = clear;
+        expected[2] = Pixel::ZERO;
+        expected[3] = Pixel::ZERO;
+        assert_eq!(frames[3], expected);
+    }
+}
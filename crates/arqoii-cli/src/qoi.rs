@@ -1,19 +1,119 @@
 use std::path::Path;
 
-use arqoii::{
-    decode::QoiDecoder,
-    encode::QoiEncoder,
-    types::{Pixel, QoiChannels, QoiColorSpace, QoiHeader},
-};
+use arqoii::prelude::*;
 
 pub fn save(channels: QoiChannels, (width, height): (u32, u32), px: &[Pixel], dest: &Path) {
     let header = QoiHeader::new(width, height, channels, QoiColorSpace::SRgbWithLinearAlpha);
-    let qoi = QoiEncoder::new(header, px.iter().cloned()).collect::<Vec<_>>();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(dest).unwrap());
+    QoiEncoder::new(header, px.iter().copied())
+        .write_to(&mut file)
+        .unwrap();
+}
+
+/// Read the content of a qoi file
+#[cfg(not(feature = "mmap"))]
+pub fn read(src: &Path) -> std::io::Result<Vec<u8>> {
+    std::fs::read(src)
+}
 
-    std::fs::write(dest, qoi).unwrap();
+/// Memory map a qoi file, instead of reading all of it into memory
+///
+/// The map is only sound as long as no other process modifies or truncates the file while it is mapped
+#[cfg(feature = "mmap")]
+pub fn read(src: &Path) -> std::io::Result<memmap2::Mmap> {
+    let file = std::fs::File::open(src)?;
+    // Safety: the CLI assumes that no other process modifies or truncates the input files while it runs.
+    // Modifying the file behind the `&[u8]` of the map would be undefined behavior,
+    // and accessing the map after the file has been truncated raises SIGBUS
+    unsafe { memmap2::Mmap::map(&file) }
 }
 
 pub fn load(data: &[u8]) -> (QoiHeader, Vec<Pixel>) {
-    let (header, pixel) = QoiDecoder::new(data.iter().copied()).unwrap();
+    let (header, pixel) = QoiDecoder::from_slice(data).unwrap();
     (header, pixel.collect())
 }
+
+/// Print the header followed by every chunk with its byte offset and the pixel it decodes to
+pub fn inspect(data: &[u8], limit: Option<usize>) {
+    let Some((header, mut pixels)) = QoiDecoder::from_slice(data) else {
+        println!("  not a qoi file");
+        return;
+    };
+
+    println!("  width:       {}", header.width);
+    println!("  height:      {}", header.height);
+    println!("  channels:    {}", header.channels);
+    println!("  color space: {}", header.color_space);
+
+    let chunks = QoiChunkDecoder::new(data[14..].iter().copied()).with_offsets();
+    for (offset, chunk) in chunks.take(limit.unwrap_or(usize::MAX)) {
+        // a run repeats the same pixel, so the last pixel of the chunk represents all of them
+        let Some(px) = pixels.nth(chunk.pixel_count() as usize - 1) else {
+            break;
+        };
+        println!(
+            "  {:>8}: {:<32} ({}, {}, {}, {})",
+            offset + 14,
+            chunk.to_string(),
+            px.r,
+            px.g,
+            px.b,
+            px.a
+        );
+    }
+}
+
+/// Print the first differing byte of two qoi files, the chunks around it on both sides
+/// and whether both files decode to the same pixels
+pub fn diff((a_path, a): (&Path, &[u8]), (b_path, b): (&Path, &[u8])) {
+    let first_difference = a
+        .iter()
+        .zip(b)
+        .position(|(a, b)| a != b)
+        .or_else(|| (a.len() != b.len()).then_some(a.len().min(b.len())));
+
+    let Some(offset) = first_difference else {
+        println!(
+            "{} and {} are identical",
+            a_path.display(),
+            b_path.display()
+        );
+        return;
+    };
+    println!("first difference at byte {offset}");
+
+    for (path, data) in [(a_path, a), (b_path, b)] {
+        println!("{}:", path.display());
+        if offset < 14 {
+            println!("  in the header");
+            continue;
+        }
+
+        let chunks = QoiChunkDecoder::new(data[14..].iter().copied())
+            .with_offsets()
+            .collect::<Vec<_>>();
+        // the chunk containing the differing byte, or the last one if the difference is in the footer
+        let containing = chunks
+            .iter()
+            .rposition(|(start, _)| start + 14 <= offset)
+            .unwrap_or(0);
+
+        for (idx, (start, chunk)) in chunks
+            .iter()
+            .enumerate()
+            .skip(containing.saturating_sub(2))
+            .take(5)
+        {
+            let marker = if idx == containing { ">" } else { " " };
+            println!("{marker} {:>8}: {}", start + 14, chunk);
+        }
+    }
+
+    let decode = |data| {
+        QoiDecoder::from_slice(data).map(|(header, pixels)| (header, pixels.collect::<Vec<_>>()))
+    };
+    match (decode(a), decode(b)) {
+        (Some(a), Some(b)) if a == b => println!("the decoded pixels match"),
+        _ => println!("the decoded pixels differ"),
+    }
+}
@@ -1,17 +1,32 @@
 #![no_std]
 
+use core::ops::RangeInclusive;
+
 /// The byte sequence beginning the **Qoi F**ormat Header
 pub const QOI_MAGIC: [u8; 4] = *b"qoif";
 
 /// The byte sequence marking the end of a Qoi File
 pub const QOI_FOOTER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
 
+/// Check whether the bytes start with [`QOI_MAGIC`], a quick check for candidate qoi files
+pub fn starts_with_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(&QOI_MAGIC)
+}
+
+/// Check whether the bytes end with [`QOI_FOOTER`], a quick check for complete qoi files
+pub fn ends_with_valid_footer(bytes: &[u8]) -> bool {
+    bytes.ends_with(&QOI_FOOTER)
+}
+
 /// A single RGB/RGBA pixel
 ///
 /// In case of RGB the alpha value should always be 255
 ///
 /// For RGBA the values should be un-premultiplied alpha
-#[derive(PartialEq, Eq, Debug, Clone)]
+///
+/// The layout is guaranteed to be the four channels in order r, g, b, a, without any padding
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[repr(C)]
 pub struct Pixel {
     pub r: u8,
     pub g: u8,
@@ -19,6 +34,30 @@ pub struct Pixel {
     pub a: u8,
 }
 
+// the index is touched for nearly every pixel, keep it a dense 256 byte block
+const _: () = assert!(core::mem::size_of::<Pixel>() == 4);
+const _: () = assert!(core::mem::align_of::<Pixel>() == 1);
+const _: () = assert!(core::mem::size_of::<[Pixel; 64]>() == 256);
+
+// the channels are laid out in the order r, g, b, a, so a &[Pixel] can be viewed as rgba bytes
+const _: () = {
+    let pixel = Pixel::ZERO;
+    let base = core::ptr::addr_of!(pixel).cast::<u8>();
+    // SAFETY: all pointers point into the same local
+    unsafe {
+        assert!(core::ptr::addr_of!(pixel.r).offset_from(base) == 0);
+        assert!(core::ptr::addr_of!(pixel.g).offset_from(base) == 1);
+        assert!(core::ptr::addr_of!(pixel.b).offset_from(base) == 2);
+        assert!(core::ptr::addr_of!(pixel.a).offset_from(base) == 3);
+    }
+};
+
+// SAFETY: Pixel consists of four u8 without padding, see the assertions above, so any bit pattern is valid
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Pixel {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Pixel {}
+
 impl Pixel {
     /// A Pixel with all channels set to 0
     pub const ZERO: Self = Pixel {
@@ -36,6 +75,32 @@ impl Pixel {
         Self { r, g, b, a }
     }
 
+    /// Unpack a Pixel from a `u32` of the form `0xRRGGBBAA`, i.e. red in the most significant byte
+    ///
+    /// The ordering refers to the value, not its in-memory representation, so this is independent of the endianness
+    pub fn from_rgba_u32(rgba: u32) -> Self {
+        let [r, g, b, a] = rgba.to_be_bytes();
+        Self::rgba(r, g, b, a)
+    }
+
+    /// Pack the Pixel into a `u32` of the form `0xRRGGBBAA`, see [`Pixel::from_rgba_u32`]
+    pub fn to_rgba_u32(&self) -> u32 {
+        u32::from_be_bytes([self.r, self.g, self.b, self.a])
+    }
+
+    /// Unpack a Pixel from a `u32` of the form `0xAARRGGBB`, i.e. alpha in the most significant byte
+    ///
+    /// The ordering refers to the value, not its in-memory representation, so this is independent of the endianness
+    pub fn from_argb_u32(argb: u32) -> Self {
+        let [a, r, g, b] = argb.to_be_bytes();
+        Self::rgba(r, g, b, a)
+    }
+
+    /// Pack the Pixel into a `u32` of the form `0xAARRGGBB`, see [`Pixel::from_argb_u32`]
+    pub fn to_argb_u32(&self) -> u32 {
+        u32::from_be_bytes([self.a, self.r, self.g, self.b])
+    }
+
     /// Calculate the Pixel Hash as described by the Qoi Specification
     pub fn pixel_hash(&self) -> u8 {
         (((self.r as usize) * 3
@@ -44,9 +109,64 @@ impl Pixel {
             + (self.a as usize) * 11)
             % 64) as u8
     }
+
+    /// Compare only the color channels of two Pixels, ignoring alpha
+    pub fn eq_rgb(&self, other: &Pixel) -> bool {
+        self.r == other.r && self.g == other.g && self.b == other.b
+    }
+
+    /// Calculate the luminance of the Pixel, ignoring alpha
+    ///
+    /// Uses the Rec. 709 coefficients `0.2126 R + 0.7152 G + 0.0722 B`, rounded to the nearest integer
+    pub fn luminance(&self) -> u8 {
+        ((self.r as u32 * 2126 + self.g as u32 * 7152 + self.b as u32 * 722 + 5000) / 10000) as u8
+    }
+
+    /// Convert the Pixel to grayscale by setting all color channels to its [`Pixel::luminance`]
+    ///
+    /// The alpha channel is kept as is
+    pub fn to_grayscale(&self) -> Pixel {
+        let l = self.luminance();
+        Pixel::rgba(l, l, l, self.a)
+    }
+
+    /// Convert the un-premultiplied Pixel into premultiplied alpha, i.e. multiply every color channel with alpha
+    ///
+    /// Each channel becomes `c * a / 255` rounded to the nearest integer,
+    /// so a fully transparent Pixel always ends up with all channels zero
+    pub fn to_premultiplied(&self) -> Pixel {
+        let premultiply = |c: u8| ((c as u32 * self.a as u32 + 127) / 255) as u8;
+        Pixel::rgba(
+            premultiply(self.r),
+            premultiply(self.g),
+            premultiply(self.b),
+            self.a,
+        )
+    }
+
+    /// Apply the differences of a Diff Chunk to this Pixel, the alpha channel is carried over
+    ///
+    /// All channels wrap around, e.g. 255 + 1 = 0 and 0 - 2 = 254
+    pub fn apply_diff(&self, dr: i8, dg: i8, db: i8) -> Pixel {
+        Pixel {
+            r: self.r.wrapping_add_signed(dr),
+            g: self.g.wrapping_add_signed(dg),
+            b: self.b.wrapping_add_signed(db),
+            a: self.a,
+        }
+    }
+
+    /// Apply the differences of a Luma Chunk to this Pixel, the alpha channel is carried over
+    ///
+    /// The red and blue differences are relative to the green difference,
+    /// all channels wrap around just like for [`Pixel::apply_diff`]
+    pub fn apply_luma(&self, dg: i8, dr_dg: i8, db_dg: i8) -> Pixel {
+        self.apply_diff(dr_dg.wrapping_add(dg), dg, db_dg.wrapping_add(dg))
+    }
 }
 
 /// The internal state of a Qoi{De,En}coder
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CoderState {
     pub previous: Pixel,
     pub index: [Pixel; 64],
@@ -63,22 +183,74 @@ impl Default for CoderState {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl CoderState {
+    /// The index slot a pixel is stored in
+    ///
+    /// Encoder and decoder access the index only through this and [`CoderState::update_index`],
+    /// so this is the single place deciding the slot, currently [`Pixel::pixel_hash`] as fixed by the spec
+    pub fn index_slot(pixel: &Pixel) -> u8 {
+        pixel.pixel_hash()
+    }
+
+    /// Store the pixel in its slot of the index
+    pub fn update_index(&mut self, pixel: Pixel) {
+        self.index[Self::index_slot(&pixel) as usize] = pixel;
+    }
+
+    /// Reset the state to its initial value, equivalent to [`CoderState::default`] but in place
+    pub fn clear(&mut self) {
+        self.previous = Pixel::rgba(0, 0, 0, 255);
+        self.index.fill(Pixel::ZERO);
+        self.run = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum QoiChannels {
     Rgb = 3,
     Rgba = 4,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum QoiColorSpace {
     SRgbWithLinearAlpha = 0,
     AllChannelsLinear = 1,
 }
 
+impl QoiChannels {
+    /// The smallest channel count able to represent all the pixels,
+    /// i.e. [`QoiChannels::Rgb`] if all pixels are fully opaque and [`QoiChannels::Rgba`] otherwise
+    pub fn from_pixels(pixels: &[Pixel]) -> Self {
+        if pixels.iter().all(|px| px.a == 255) {
+            QoiChannels::Rgb
+        } else {
+            QoiChannels::Rgba
+        }
+    }
+}
+
+impl core::fmt::Display for QoiChannels {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            QoiChannels::Rgb => "RGB",
+            QoiChannels::Rgba => "RGBA",
+        })
+    }
+}
+
+impl core::fmt::Display for QoiColorSpace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            QoiColorSpace::SRgbWithLinearAlpha => "sRGB (linear alpha)",
+            QoiColorSpace::AllChannelsLinear => "linear",
+        })
+    }
+}
+
 /// A struct representing the Qoi Format File Header
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct QoiHeader {
     pub width: u32,
     pub height: u32,
@@ -86,6 +258,13 @@ pub struct QoiHeader {
     pub color_space: QoiColorSpace,
 }
 
+/// A zero sized [`QoiChannels::Rgba`] header in [`QoiColorSpace::SRgbWithLinearAlpha`]
+impl Default for QoiHeader {
+    fn default() -> Self {
+        Self::new(0, 0, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha)
+    }
+}
+
 impl QoiHeader {
     pub fn new(width: u32, height: u32, channels: QoiChannels, color_space: QoiColorSpace) -> Self {
         Self {
@@ -96,6 +275,67 @@ impl QoiHeader {
         }
     }
 
+    /// The number of pixels in the image, i.e. width * height
+    ///
+    /// This can exceed `u32::MAX`, so pixel counts and offsets are computed as `u64`.
+    /// Streaming APIs support the full range of up to `(2^32 - 1)^2` pixels,
+    /// APIs holding all pixels in memory are additionally limited to `usize::MAX` pixels
+    pub fn pixel_count(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Parse a header from its 14 bytes
+    pub fn from_bytes(bytes: &[u8; 14]) -> Result<Self, HeaderError> {
+        Self::read_from(bytes.iter().copied())
+    }
+
+    /// Parse a header from the next 14 bytes of `iter`, without reading any further
+    ///
+    /// Pass the iterator by reference to continue reading the chunk data afterwards
+    pub fn read_from(mut iter: impl Iterator<Item = u8>) -> Result<Self, HeaderError> {
+        let mut next = || iter.next().ok_or(HeaderError::UnexpectedEof);
+
+        let magic = [next()?, next()?, next()?, next()?];
+
+        if magic != QOI_MAGIC {
+            return Err(HeaderError::InvalidMagic(magic));
+        }
+
+        let width = u32::from_be_bytes([next()?, next()?, next()?, next()?]);
+        let height = u32::from_be_bytes([next()?, next()?, next()?, next()?]);
+        let channels = match next()? {
+            3 => QoiChannels::Rgb,
+            4 => QoiChannels::Rgba,
+            other => return Err(HeaderError::InvalidChannels(other)),
+        };
+        let color_space = match next()? {
+            0 => QoiColorSpace::SRgbWithLinearAlpha,
+            1 => QoiColorSpace::AllChannelsLinear,
+            other => return Err(HeaderError::InvalidColorSpace(other)),
+        };
+
+        Ok(Self::new(width, height, channels, color_space))
+    }
+
+    /// The smallest possible size of a qoi file with this header, including header and footer
+    ///
+    /// This is the size if every chunk is a run of 62 pixels, saturating at `usize::MAX`
+    pub fn min_file_len(&self) -> usize {
+        let chunks = self.pixel_count().div_ceil(QoiChunk::MAX_RUN as u64);
+        usize::try_from(chunks + 14 + QOI_FOOTER.len() as u64).unwrap_or(usize::MAX)
+    }
+
+    /// The largest possible size of a qoi file with this header, including header and footer
+    ///
+    /// This is the size if every pixel needs an Rgba Chunk,
+    /// as encoders need not enforce the channels of the header.
+    /// Returns `None` if the size does not fit into usize
+    pub fn max_file_len(&self) -> Option<usize> {
+        let chunks = self.pixel_count().checked_mul(5)?;
+        let len = chunks.checked_add(14 + QOI_FOOTER.len() as u64)?;
+        usize::try_from(len).ok()
+    }
+
     pub fn to_bytes(&self) -> [u8; 14] {
         let mut bytes = [0; 14];
 
@@ -111,13 +351,56 @@ impl QoiHeader {
             bytes[i + QOI_MAGIC.len() + (u32::BITS / 8) as usize] = b;
         }
 
-        bytes[QOI_MAGIC.len() + 2 * (u32::BITS / 8) as usize] = self.channels.clone() as u8;
-        bytes[QOI_MAGIC.len() + 2 * (u32::BITS / 8) as usize + 1] = self.color_space.clone() as u8;
+        bytes[QOI_MAGIC.len() + 2 * (u32::BITS / 8) as usize] = self.channels as u8;
+        bytes[QOI_MAGIC.len() + 2 * (u32::BITS / 8) as usize + 1] = self.color_space as u8;
 
         bytes
     }
 }
 
+/// Parse a header from the first 14 bytes of a slice, any further bytes are ignored
+///
+/// Fails with [`HeaderError::UnexpectedEof`] if the slice is shorter than 14 bytes
+impl TryFrom<&[u8]> for QoiHeader {
+    type Error = HeaderError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes = bytes
+            .get(..14)
+            .and_then(|bytes| <&[u8; 14]>::try_from(bytes).ok())
+            .ok_or(HeaderError::UnexpectedEof)?;
+        Self::from_bytes(bytes)
+    }
+}
+
+/// The reasons parsing a [`QoiHeader`] can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The data ended before the header was complete
+    UnexpectedEof,
+    /// The data did not start with [`QOI_MAGIC`]
+    InvalidMagic([u8; 4]),
+    /// The header contained a channel count other than 3 or 4
+    InvalidChannels(u8),
+    /// The header contained a color space other than 0 or 1
+    InvalidColorSpace(u8),
+}
+
+impl core::fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HeaderError::UnexpectedEof => write!(f, "unexpected end of data"),
+            HeaderError::InvalidMagic(magic) => write!(f, "invalid magic bytes {magic:?}"),
+            HeaderError::InvalidChannels(channels) => {
+                write!(f, "invalid channel count {channels}")
+            }
+            HeaderError::InvalidColorSpace(color_space) => {
+                write!(f, "invalid color space {color_space}")
+            }
+        }
+    }
+}
+
 /// An individual Chunk,
 /// representing between 1 and 62 pixel
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -145,32 +428,50 @@ pub enum QoiChunk {
 }
 
 impl QoiChunk {
-    /// Create a new Run Chunk, run needs to be in the range 0..=62
+    /// The longest run a single Run Chunk can represent
+    ///
+    /// The run lengths 63 and 64 would collide with the tags of the Rgb and Rgba Chunks
+    pub const MAX_RUN: u8 = 62;
+
+    /// The highest index an Index Chunk can refer to
+    pub const MAX_INDEX: u8 = 63;
+
+    /// The range of dr, dg and db of a Diff Chunk
+    pub const DIFF_RANGE: RangeInclusive<i8> = -2..=1;
+
+    /// The range of dg of a Luma Chunk
+    pub const LUMA_DG_RANGE: RangeInclusive<i8> = -32..=31;
+
+    /// The range of dr_dg and db_dg of a Luma Chunk
+    pub const LUMA_DR_DG_RANGE: RangeInclusive<i8> = -8..=7;
+
+    /// Create a new Run Chunk, run needs to be in the range 1..=[`QoiChunk::MAX_RUN`]
     pub fn new_run(run: u8) -> Self {
-        debug_assert!(0 < run && run <= 62);
+        debug_assert!(0 < run && run <= Self::MAX_RUN);
         Self::Run { run }
     }
 
-    // Create a new Index Chunk, index needs to be at most 63
+    /// Create a new Index Chunk, index needs to be at most [`QoiChunk::MAX_INDEX`]
     pub fn new_index(idx: u8) -> Self {
-        debug_assert!(idx <= 63);
+        debug_assert!(idx <= Self::MAX_INDEX);
         Self::Index { idx }
     }
 
-    // Create a new Diff Chunk, all arguments need to be in the range -1..=1
+    /// Create a new Diff Chunk, all arguments need to be in [`QoiChunk::DIFF_RANGE`]
     pub fn new_diff(dr: i8, dg: i8, db: i8) -> Self {
-        debug_assert!((-2..=1).contains(&dr));
-        debug_assert!((-2..=1).contains(&dg));
-        debug_assert!((-2..=1).contains(&db));
+        debug_assert!(Self::DIFF_RANGE.contains(&dr));
+        debug_assert!(Self::DIFF_RANGE.contains(&dg));
+        debug_assert!(Self::DIFF_RANGE.contains(&db));
 
         Self::Diff { dr, dg, db }
     }
 
-    // Create a new Luma Chunk, dg needs to be in the range -32..=31, dr_dg and db_dg need to be in the range -8..=7
+    /// Create a new Luma Chunk, dg needs to be in [`QoiChunk::LUMA_DG_RANGE`],
+    /// dr_dg and db_dg need to be in [`QoiChunk::LUMA_DR_DG_RANGE`]
     pub fn new_luma(dg: i8, dr_dg: i8, db_dg: i8) -> Self {
-        debug_assert!((-32..=31).contains(&dg));
-        debug_assert!((-8..=7).contains(&dr_dg));
-        debug_assert!((-8..=7).contains(&db_dg));
+        debug_assert!(Self::LUMA_DG_RANGE.contains(&dg));
+        debug_assert!(Self::LUMA_DR_DG_RANGE.contains(&dr_dg));
+        debug_assert!(Self::LUMA_DR_DG_RANGE.contains(&db_dg));
 
         Self::Luma { dg, dr_dg, db_dg }
     }
@@ -185,6 +486,65 @@ impl QoiChunk {
         Self::Rgba { r, g, b, a }
     }
 
+    /// The number of pixels represented by this Chunk
+    ///
+    /// This is `run` for a Run Chunk and 1 for all other Chunks
+    pub fn pixel_count(&self) -> u8 {
+        match self {
+            QoiChunk::Run { run } => *run,
+            _ => 1,
+        }
+    }
+
+    /// The number of bytes this Chunk takes up when encoded
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            QoiChunk::Rgba { .. } => 5,
+            QoiChunk::Rgb { .. } => 4,
+            QoiChunk::Luma { .. } => 2,
+            QoiChunk::Index { .. } | QoiChunk::Diff { .. } | QoiChunk::Run { .. } => 1,
+        }
+    }
+
+    /// Parse a single Chunk from the start of `bytes`, returning it together with the number of bytes it took up
+    ///
+    /// Returns `None` if `bytes` ends within the Chunk.
+    /// The footer is not special cased, it reads as Index Chunks.
+    pub fn decode(bytes: &[u8]) -> Option<(QoiChunk, usize)> {
+        let (&init, rest) = bytes.split_first()?;
+
+        let chunk = match init {
+            0b11111111 => match *rest {
+                [r, g, b, a, ..] => QoiChunk::new_rgba(r, g, b, a),
+                _ => return None,
+            },
+            0b11111110 => match *rest {
+                [r, g, b, ..] => QoiChunk::new_rgb(r, g, b),
+                _ => return None,
+            },
+            _ => match init >> 6 {
+                0b00 => QoiChunk::new_index(init & 0b00111111),
+                0b01 => QoiChunk::new_diff(
+                    ((init >> 4) & 0b11) as i8 - 2,
+                    ((init >> 2) & 0b11) as i8 - 2,
+                    (init & 0b11) as i8 - 2,
+                ),
+                0b10 => {
+                    let next = *rest.first()?;
+                    QoiChunk::new_luma(
+                        (init & 0b00111111) as i8 - 32,
+                        (next >> 4) as i8 - 8,
+                        (next & 0b1111) as i8 - 8,
+                    )
+                }
+                _ => QoiChunk::new_run((init & 0b00111111) + 1),
+            },
+        };
+
+        let len = chunk.encoded_len();
+        Some((chunk, len))
+    }
+
     /// Write the Chunk into the provided ChunkBuf
     fn write_to_chunk_buffer(&self, buf: &mut ChunkBuf) {
         match self.clone() {
@@ -218,13 +578,48 @@ impl QoiChunk {
             QoiChunk::Run { run } => {
                 // [ 1 1 run run run run run run ]
                 // Note: [ 1 1 1 1 1 1 1 1 ] & [ 1 1 1 1 1 1 1 0 ] are invalid here
-                debug_assert!(run <= 62);
+                debug_assert!(run <= QoiChunk::MAX_RUN);
                 buf.set([0b11000000 | (run - 1)]);
             }
         }
     }
 }
 
+/// Formats the Chunk in a human readable form, e.g. `Diff dr=-1 dg=0 db=+1` or `Run 15`
+impl core::fmt::Display for QoiChunk {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        /// Format a difference with an explicit sign, except for zero
+        struct Delta(i8);
+
+        impl core::fmt::Display for Delta {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                if self.0 > 0 {
+                    write!(f, "+{}", self.0)
+                } else {
+                    write!(f, "{}", self.0)
+                }
+            }
+        }
+
+        match *self {
+            QoiChunk::Rgb { r, g, b } => write!(f, "Rgb r={r} g={g} b={b}"),
+            QoiChunk::Rgba { r, g, b, a } => write!(f, "Rgba r={r} g={g} b={b} a={a}"),
+            QoiChunk::Index { idx } => write!(f, "Index {idx}"),
+            QoiChunk::Diff { dr, dg, db } => {
+                write!(f, "Diff dr={} dg={} db={}", Delta(dr), Delta(dg), Delta(db))
+            }
+            QoiChunk::Luma { dg, dr_dg, db_dg } => write!(
+                f,
+                "Luma dg={} dr-dg={} db-dg={}",
+                Delta(dg),
+                Delta(dr_dg),
+                Delta(db_dg)
+            ),
+            QoiChunk::Run { run } => write!(f, "Run {run}"),
+        }
+    }
+}
+
 impl IntoIterator for QoiChunk {
     type Item = u8;
 
@@ -237,6 +632,19 @@ impl IntoIterator for QoiChunk {
     }
 }
 
+/// Iterate over the bytes of the Chunk without consuming it
+impl IntoIterator for &QoiChunk {
+    type Item = u8;
+
+    type IntoIter = ChunkBuf;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let mut buf = ChunkBuf::new();
+        self.write_to_chunk_buffer(&mut buf);
+        buf
+    }
+}
+
 /// A buffer for the bytes of a single Chunk
 ///
 /// used to iterate over the bytes of a Chunk
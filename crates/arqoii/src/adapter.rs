@@ -0,0 +1,204 @@
+use core::iter::FusedIterator;
+
+use crate::types::{Pixel, QoiHeader};
+
+/// Adapters for iterators over [`Pixel`]s
+pub trait PixelIteratorExt: Iterator<Item = Pixel> + Sized {
+    /// Force the alpha channel of every pixel to 255
+    ///
+    /// The resulting pixels are always opaque, so the encoder will never emit an Rgba chunk for them
+    /// and the [`QoiChannels::Rgb`](crate::types::QoiChannels::Rgb) channel declaration of the header holds.
+    ///
+    /// # Note
+    /// this is lossy for images containing (partially) transparent pixels!
+    fn drop_alpha(self) -> DropAlpha<Self> {
+        DropAlpha { pixels: self }
+    }
+
+    /// Convert the color channels from sRGB to linear, leaving the alpha channel as is
+    ///
+    /// This applies the sRGB transfer function `c <= 0.04045 ? c / 12.92 : ((c + 0.055) / 1.055)^2.4`
+    /// to each channel scaled to `0.0..=1.0`, see [`QoiColorSpace`](crate::types::QoiColorSpace).
+    ///
+    /// # Note
+    /// this is lossy, as 8 bits are not enough to hold the result without rounding,
+    /// especially dark colors collapse and don't survive a conversion back with [`PixelIteratorExt::to_srgb`]
+    fn to_linear(self) -> ConvertColorSpace<Self> {
+        ConvertColorSpace {
+            pixels: self,
+            table: &SRGB_TO_LINEAR,
+        }
+    }
+
+    /// Convert the color channels from linear to sRGB, leaving the alpha channel as is
+    ///
+    /// This applies the inverse sRGB transfer function `c <= 0.0031308 ? 12.92 * c : 1.055 * c^(1/2.4) - 0.055`
+    /// to each channel scaled to `0.0..=1.0`, the inverse of [`PixelIteratorExt::to_linear`].
+    ///
+    /// # Note
+    /// this is lossy, as 8 bits are not enough to hold the result without rounding
+    fn to_srgb(self) -> ConvertColorSpace<Self> {
+        ConvertColorSpace {
+            pixels: self,
+            table: &LINEAR_TO_SRGB,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Pixel>> PixelIteratorExt for I {}
+
+/// Make a pixel stream yield exactly [`QoiHeader::pixel_count`] pixels
+///
+/// Excess pixels are dropped, a short stream is padded with the fill pixel,
+/// which defaults to opaque black and can be changed with [`FitToHeader::fill`].
+/// Feeding the result into [`QoiEncoder`](crate::encode::QoiEncoder) always produces a correctly sized file.
+pub fn fit_to_header<I: IntoIterator<Item = Pixel>>(
+    pixels: I,
+    header: &QoiHeader,
+) -> FitToHeader<I::IntoIter> {
+    FitToHeader {
+        pixels: Some(pixels.into_iter()),
+        remaining: header.pixel_count(),
+        fill: Pixel::rgb(0, 0, 0),
+    }
+}
+
+/// An iterator truncating or padding a pixel stream to the pixel count of a header
+///
+/// Created by [`fit_to_header`]
+pub struct FitToHeader<I> {
+    pixels: Option<I>,
+    remaining: u64,
+    fill: Pixel,
+}
+
+impl<I> FitToHeader<I> {
+    /// Set the pixel used to pad a short stream
+    pub fn fill(mut self, fill: Pixel) -> Self {
+        self.fill = fill;
+        self
+    }
+}
+
+impl<I: Iterator<Item = Pixel>> Iterator for FitToHeader<I> {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        match self.pixels.as_mut().and_then(Iterator::next) {
+            Some(pixel) => Some(pixel),
+            None => {
+                self.pixels = None;
+                Some(self.fill)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match usize::try_from(self.remaining) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
+}
+
+impl<I: Iterator<Item = Pixel>> FusedIterator for FitToHeader<I> {}
+
+/// An iterator setting the alpha channel of every pixel to 255
+///
+/// Created by [`PixelIteratorExt::drop_alpha`]
+pub struct DropAlpha<I> {
+    pixels: I,
+}
+
+impl<I: Iterator<Item = Pixel>> Iterator for DropAlpha<I> {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pixels.next().map(|pixel| Pixel { a: 255, ..pixel })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pixels.size_hint()
+    }
+}
+
+impl<I> FusedIterator for DropAlpha<I>
+where
+    DropAlpha<I>: Iterator,
+    I: FusedIterator,
+{
+}
+
+/// An iterator converting the color channels of every pixel between sRGB and linear
+///
+/// Created by [`PixelIteratorExt::to_linear`] and [`PixelIteratorExt::to_srgb`]
+pub struct ConvertColorSpace<I> {
+    pixels: I,
+    table: &'static [u8; 256],
+}
+
+impl<I: Iterator<Item = Pixel>> Iterator for ConvertColorSpace<I> {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pixels.next().map(|pixel| Pixel {
+            r: self.table[pixel.r as usize],
+            g: self.table[pixel.g as usize],
+            b: self.table[pixel.b as usize],
+            a: pixel.a,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pixels.size_hint()
+    }
+}
+
+impl<I> FusedIterator for ConvertColorSpace<I>
+where
+    ConvertColorSpace<I>: Iterator,
+    I: FusedIterator,
+{
+}
+
+// the tables are computed with f64 precision and rounded to the nearest value,
+// as the transfer function needs powf which is not available in no_std
+
+/// The result of [`PixelIteratorExt::to_linear`] for every 8-bit value
+const SRGB_TO_LINEAR: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3,
+    4, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 8, 8, 8, 8, 9, 9, 9, 10, 10, 10, 11, 11, 12,
+    12, 12, 13, 13, 13, 14, 14, 15, 15, 16, 16, 17, 17, 17, 18, 18, 19, 19, 20, 20, 21, 22, 22, 23,
+    23, 24, 24, 25, 25, 26, 27, 27, 28, 29, 29, 30, 30, 31, 32, 32, 33, 34, 35, 35, 36, 37, 37, 38,
+    39, 40, 41, 41, 42, 43, 44, 45, 45, 46, 47, 48, 49, 50, 51, 51, 52, 53, 54, 55, 56, 57, 58, 59,
+    60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 76, 77, 78, 79, 80, 81, 82, 84, 85,
+    86, 87, 88, 90, 91, 92, 93, 95, 96, 97, 99, 100, 101, 103, 104, 105, 107, 108, 109, 111, 112,
+    114, 115, 116, 118, 119, 121, 122, 124, 125, 127, 128, 130, 131, 133, 134, 136, 138, 139, 141,
+    142, 144, 146, 147, 149, 151, 152, 154, 156, 157, 159, 161, 163, 164, 166, 168, 170, 171, 173,
+    175, 177, 179, 181, 183, 184, 186, 188, 190, 192, 194, 196, 198, 200, 202, 204, 206, 208, 210,
+    212, 214, 216, 218, 220, 222, 224, 226, 229, 231, 233, 235, 237, 239, 242, 244, 246, 248, 250,
+    253, 255,
+];
+
+/// The result of [`PixelIteratorExt::to_srgb`] for every 8-bit value
+const LINEAR_TO_SRGB: [u8; 256] = [
+    0, 13, 22, 28, 34, 38, 42, 46, 50, 53, 56, 59, 61, 64, 66, 69, 71, 73, 75, 77, 79, 81, 83, 85,
+    86, 88, 90, 92, 93, 95, 96, 98, 99, 101, 102, 104, 105, 106, 108, 109, 110, 112, 113, 114, 115,
+    117, 118, 119, 120, 121, 122, 124, 125, 126, 127, 128, 129, 130, 131, 132, 133, 134, 135, 136,
+    137, 138, 139, 140, 141, 142, 143, 144, 145, 146, 147, 148, 148, 149, 150, 151, 152, 153, 154,
+    155, 155, 156, 157, 158, 159, 159, 160, 161, 162, 163, 163, 164, 165, 166, 167, 167, 168, 169,
+    170, 170, 171, 172, 173, 173, 174, 175, 175, 176, 177, 178, 178, 179, 180, 180, 181, 182, 182,
+    183, 184, 185, 185, 186, 187, 187, 188, 189, 189, 190, 190, 191, 192, 192, 193, 194, 194, 195,
+    196, 196, 197, 197, 198, 199, 199, 200, 200, 201, 202, 202, 203, 203, 204, 205, 205, 206, 206,
+    207, 208, 208, 209, 209, 210, 210, 211, 212, 212, 213, 213, 214, 214, 215, 215, 216, 216, 217,
+    218, 218, 219, 219, 220, 220, 221, 221, 222, 222, 223, 223, 224, 224, 225, 226, 226, 227, 227,
+    228, 228, 229, 229, 230, 230, 231, 231, 232, 232, 233, 233, 234, 234, 235, 235, 236, 236, 237,
+    237, 238, 238, 238, 239, 239, 240, 240, 241, 241, 242, 242, 243, 243, 244, 244, 245, 245, 246,
+    246, 246, 247, 247, 248, 248, 249, 249, 250, 250, 251, 251, 251, 252, 252, 253, 253, 254, 254,
+    255, 255,
+];
@@ -1,55 +1,366 @@
-use arqoii_types::QOI_MAGIC;
+use core::sync::atomic::{AtomicBool, Ordering};
 
+use crate::digest::WithDigest;
+use crate::encode::QoiEncoder;
 use crate::iterator_helper::PeekN;
-use crate::types::{
-    CoderState, Pixel, QoiChannels, QoiChunk, QoiColorSpace, QoiHeader, QOI_FOOTER,
-};
+use crate::stats::WithStats;
+use crate::types::{CoderState, HeaderError, Pixel, QoiChannels, QoiChunk, QoiHeader, QOI_FOOTER};
+
+/// The reasons decoding a qoi can fail
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The data ended before the header was complete
+    UnexpectedEof,
+    /// The data did not start with [`QOI_MAGIC`]
+    InvalidMagic([u8; 4]),
+    /// The header contained a channel count other than 3 or 4
+    InvalidChannels(u8),
+    /// The header contained a color space other than 0 or 1
+    InvalidColorSpace(u8),
+    /// The number of decoded pixels did not match the width * height declared by the header
+    PixelCountMismatch { expected: u64, found: u64 },
+    /// The pixels were not followed by the footer
+    MissingFooter,
+    /// Two consecutive Index Chunks referred to the same index, which a valid encoder would encode as a run
+    RedundantIndex(u8),
+    /// A Run Chunk continued beyond the width * height pixels declared by the header
+    RunExceedsImage { run: u8, remaining: u64 },
+    /// An Index Chunk referred to an index no previous pixel was written to
+    UnwrittenIndex(u8),
+    /// Decoding was cancelled, see [`QoiDecoder::decode_cancellable`]
+    Cancelled,
+    /// A pixel of an image declaring [`QoiChannels::Rgb`] was not opaque
+    AlphaInRgbImage { alpha: u8 },
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of data"),
+            DecodeError::InvalidMagic(magic) => write!(f, "invalid magic bytes {magic:?}"),
+            DecodeError::InvalidChannels(channels) => {
+                write!(f, "invalid channel count {channels}")
+            }
+            DecodeError::InvalidColorSpace(color_space) => {
+                write!(f, "invalid color space {color_space}")
+            }
+            DecodeError::PixelCountMismatch { expected, found } => {
+                write!(f, "expected {expected} pixels, but found {found}")
+            }
+            DecodeError::MissingFooter => write!(f, "missing footer"),
+            DecodeError::RedundantIndex(idx) => {
+                write!(f, "consecutive index chunks referring to index {idx}")
+            }
+            DecodeError::RunExceedsImage { run, remaining } => {
+                write!(f, "run of {run} pixels, but only {remaining} pixels remain")
+            }
+            DecodeError::UnwrittenIndex(idx) => {
+                write!(f, "index chunk referring to never written index {idx}")
+            }
+            DecodeError::Cancelled => write!(f, "decoding was cancelled"),
+            DecodeError::AlphaInRgbImage { alpha } => {
+                write!(f, "pixel with alpha {alpha} in an rgb image")
+            }
+        }
+    }
+}
+
+impl From<HeaderError> for DecodeError {
+    fn from(error: HeaderError) -> Self {
+        match error {
+            HeaderError::UnexpectedEof => DecodeError::UnexpectedEof,
+            HeaderError::InvalidMagic(magic) => DecodeError::InvalidMagic(magic),
+            HeaderError::InvalidChannels(channels) => DecodeError::InvalidChannels(channels),
+            HeaderError::InvalidColorSpace(color_space) => {
+                DecodeError::InvalidColorSpace(color_space)
+            }
+        }
+    }
+}
+
+/// Decode a complete qoi from a byte slice
+///
+/// This never panics, regardless of the provided bytes,
+/// invalid data either results in an error or in a malformed image.
+///
+/// At most width * height pixels are decoded, but fewer pixels are returned if the data ends early.
+/// The pixel buffer is not pre-allocated based on the header, so an absurd header alone can't exhaust memory.
+#[cfg(feature = "alloc")]
+pub fn decode_fuzz_safe(bytes: &[u8]) -> Result<(QoiHeader, alloc::vec::Vec<Pixel>), DecodeError> {
+    let options = DecoderOptions::new().enforce_pixel_count(true);
+    let (header, decoder) = QoiDecoder::with_options(bytes.iter().copied(), options)?;
+    Ok((header, decoder.collect()))
+}
+
+/// Decode a complete qoi from a byte slice into 4 bytes per pixel in the channel order b, g, r, a
+///
+/// This is the counterpart to [`encode_bgra_bytes`](crate::encode::encode_bgra_bytes),
+/// at most width * height pixels are decoded, see [`decode_fuzz_safe`]
+#[cfg(feature = "alloc")]
+pub fn decode_to_bgra_bytes(bytes: &[u8]) -> Result<(QoiHeader, alloc::vec::Vec<u8>), DecodeError> {
+    let options = DecoderOptions::new().enforce_pixel_count(true);
+    let (header, decoder) = QoiDecoder::with_options(bytes.iter().copied(), options)?;
+    let bgra = decoder
+        .flat_map(|pixel| [pixel.b, pixel.g, pixel.r, pixel.a])
+        .collect();
+    Ok((header, bgra))
+}
+
+/// Options controlling how strictly the decoder validates its input
+///
+/// The default options decode leniently, checking nothing but the header.
+/// Violations stop the decoder and are reported by [`QoiDecoder::finish`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DecoderOptions {
+    enforce_pixel_count: bool,
+    require_footer: bool,
+    reject_redundant_index: bool,
+    reject_bad_run: bool,
+    unwritten_index: IndexCheck,
+    warn_channel_mismatch: bool,
+    reject_alpha_in_rgb: bool,
+    clamp_to_header: bool,
+}
+
+/// How to treat Index Chunks referring to an index no previous pixel was written to
+///
+/// The index starts out zeroed, so referring to the index of [`Pixel::ZERO`] is always fine,
+/// as a real encoder can produce that. Any other unwritten index indicates a corrupted file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexCheck {
+    /// Don't check the index
+    #[default]
+    Off,
+    /// Count the offending chunks, see [`QoiDecoder::unwritten_index_count`]
+    Warn,
+    /// Stop decoding with [`DecodeError::UnwrittenIndex`]
+    Error,
+}
+
+impl DecoderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options enabling all checks, rejecting anything a valid encoder would not produce
+    pub fn strict() -> Self {
+        Self::new()
+            .enforce_pixel_count(true)
+            .require_footer(true)
+            .reject_redundant_index(true)
+            .reject_bad_run(true)
+            .check_unwritten_index(IndexCheck::Error)
+            .reject_alpha_in_rgb(true)
+    }
+
+    /// Stop after the width * height pixels declared by the header, see [`QoiDecoder::new_bounded`]
+    pub fn enforce_pixel_count(mut self, enforce_pixel_count: bool) -> Self {
+        self.enforce_pixel_count = enforce_pixel_count;
+        self
+    }
+
+    /// Report a [`DecodeError::MissingFooter`] if the pixels are not followed by the footer
+    pub fn require_footer(mut self, require_footer: bool) -> Self {
+        self.require_footer = require_footer;
+        self
+    }
+
+    /// Reject consecutive Index Chunks referring to the same index with [`DecodeError::RedundantIndex`]
+    pub fn reject_redundant_index(mut self, reject_redundant_index: bool) -> Self {
+        self.reject_redundant_index = reject_redundant_index;
+        self
+    }
+
+    /// Reject runs continuing past the end of the image with [`DecodeError::RunExceedsImage`]
+    pub fn reject_bad_run(mut self, reject_bad_run: bool) -> Self {
+        self.reject_bad_run = reject_bad_run;
+        self
+    }
+
+    /// Check Index Chunks for references to never written indices, see [`IndexCheck`]
+    pub fn check_unwritten_index(mut self, check: IndexCheck) -> Self {
+        self.unwritten_index = check;
+        self
+    }
+
+    /// Record whether an image declaring [`QoiChannels::Rgb`](crate::types::QoiChannels::Rgb) contains Rgba Chunks,
+    /// see [`QoiDecoder::channel_mismatch`]
+    ///
+    /// Such images are still decoded, the alpha channel of the Rgba Chunks is kept
+    pub fn warn_channel_mismatch(mut self, warn_channel_mismatch: bool) -> Self {
+        self.warn_channel_mismatch = warn_channel_mismatch;
+        self
+    }
+
+    /// Reject pixels that are not opaque in an image declaring [`QoiChannels::Rgb`](crate::types::QoiChannels::Rgb)
+    /// with [`DecodeError::AlphaInRgbImage`]
+    ///
+    /// Unlike [`DecoderOptions::warn_channel_mismatch`] this only catches Rgba Chunks that actually changed the alpha channel
+    pub fn reject_alpha_in_rgb(mut self, reject_alpha_in_rgb: bool) -> Self {
+        self.reject_alpha_in_rgb = reject_alpha_in_rgb;
+        self
+    }
+
+    /// Stop emitting pixels after the width * height pixels declared by the header,
+    /// skipping any extra chunks up to the footer, see [`QoiDecoder::extra_data`]
+    ///
+    /// Unlike [`DecoderOptions::enforce_pixel_count`] the footer is still found and checked by [`QoiDecoder::finish`],
+    /// so this recovers a correctly sized image from files with too many pixels.
+    /// Has no effect together with [`DecoderOptions::enforce_pixel_count`], which stops right after the last pixel
+    pub fn clamp_to_header(mut self, clamp_to_header: bool) -> Self {
+        self.clamp_to_header = clamp_to_header;
+        self
+    }
+}
+
+/// Turn chunk data back into chunks, this is the inverse of [`chunks_to_bytes`](crate::encode::chunks_to_bytes)
+///
+/// The bytes must not include the header, decoding stops at the footer
+pub fn bytes_to_chunks<B>(bytes: B) -> QoiChunkDecoder<B::IntoIter>
+where
+    B: IntoIterator<Item = u8>,
+{
+    QoiChunkDecoder::new(bytes.into_iter())
+}
+
+/// Count the chunks in chunk data, stopping at the footer
+///
+/// Like [`bytes_to_chunks`] the bytes must not include the header, so for a complete file skip the first
+/// 14 header bytes. Timing this is a simple way to measure the raw chunk decoding throughput
+pub fn count_chunks(bytes: &[u8]) -> usize {
+    bytes_to_chunks(bytes.iter().copied()).count()
+}
 
 /// A decoder for decoding bytes into qoi chunks
 ///
 /// Expects the data to not include the header
 pub struct QoiChunkDecoder<I> {
     bytes: PeekN<7, I, u8>,
+    /// the number of pixels left before the footer is expected, if known
+    pixels_until_footer: Option<u64>,
+    /// whether the decoder stopped at the footer, which is then the next thing in bytes
+    at_footer: bool,
+    /// the bytes the last chunk was decoded from
+    raw: [u8; 5],
+    raw_len: usize,
+    /// the number of bytes taken from bytes, not counting peeked bytes
+    consumed: usize,
 }
 
 impl<I> QoiChunkDecoder<I> {
+    /// Create a new chunk decoder, stopping at the first occurrence of the footer
+    ///
+    /// Note: as the footer is a valid sequence of chunks
+    /// this stops early if the footer happens to appear within the chunk data
     pub fn new(iter: I) -> QoiChunkDecoder<I>
     where
         I: Iterator<Item = u8>,
     {
         Self {
             bytes: PeekN::new(iter),
+            pixels_until_footer: None,
+            at_footer: false,
+            raw: [0; 5],
+            raw_len: 0,
+            consumed: 0,
+        }
+    }
+
+    /// Create a new chunk decoder,
+    /// that only checks for the footer after the width * height pixels from the header have been decoded
+    ///
+    /// This removes the ambiguity of chunk data that happens to contain the footer byte sequence
+    pub fn new_from_header_aware(iter: I, header: &QoiHeader) -> QoiChunkDecoder<I>
+    where
+        I: Iterator<Item = u8>,
+    {
+        Self {
+            bytes: PeekN::new(iter),
+            pixels_until_footer: Some(header.pixel_count()),
+            at_footer: false,
+            raw: [0; 5],
+            raw_len: 0,
+            consumed: 0,
+        }
+    }
+
+    /// Pair every chunk with the offset of its first byte,
+    /// relative to the start of the bytes this decoder was created with
+    pub fn with_offsets(self) -> WithOffsets<I> {
+        WithOffsets {
+            chunks: self,
+            offset: 0,
         }
     }
+
+    /// Pair every chunk with the exact bytes it was decoded from
+    #[cfg(feature = "alloc")]
+    pub fn with_raw_bytes(self) -> WithRawBytes<I> {
+        WithRawBytes { chunks: self }
+    }
+
+    /// Count the decoded chunks, see [`WithStats`]
+    pub fn with_stats(self) -> WithStats<Self> {
+        WithStats::new(self)
+    }
 }
 
 impl<I: Iterator<Item = u8>> Iterator for QoiChunkDecoder<I> {
     type Item = QoiChunk;
+
     fn next(&mut self) -> Option<Self::Item> {
-        let init = self.bytes.next()?;
+        let chunk = self.decode_next()?;
+        if let Some(pixels) = &mut self.pixels_until_footer {
+            *pixels = pixels.saturating_sub(chunk.pixel_count() as u64);
+        }
+        Some(chunk)
+    }
+}
+
+impl<I: Iterator<Item = u8>> QoiChunkDecoder<I> {
+    /// Get the next byte of the current chunk, recording it in raw
+    fn next_byte(&mut self) -> Option<u8> {
+        let byte = self.bytes.next()?;
+        self.raw[self.raw_len] = byte;
+        self.raw_len += 1;
+        self.consumed += 1;
+        Some(byte)
+    }
+
+    fn decode_next(&mut self) -> Option<QoiChunk> {
+        // all values are masked into the valid range of their chunk before calling the constructors,
+        // so their debug assertions can't be triggered by any input
+        self.at_footer = false;
+        self.raw_len = 0;
+        let init = self.next_byte()?;
 
         if init == 0b11111111 {
             // rgba
-            let r = self.bytes.next()?;
-            let g = self.bytes.next()?;
-            let b = self.bytes.next()?;
-            let a = self.bytes.next()?;
+            let r = self.next_byte()?;
+            let g = self.next_byte()?;
+            let b = self.next_byte()?;
+            let a = self.next_byte()?;
             Some(QoiChunk::new_rgba(r, g, b, a))
         } else if init == 0b11111110 {
             // rgb
-            let r = self.bytes.next()?;
-            let g = self.bytes.next()?;
-            let b = self.bytes.next()?;
+            let r = self.next_byte()?;
+            let g = self.next_byte()?;
+            let b = self.next_byte()?;
             Some(QoiChunk::new_rgb(r, g, b))
         } else {
             let short = init >> 6;
             if short == 0b00 {
                 // index
-                if init == 0 {
+                let footer_expected =
+                    !matches!(self.pixels_until_footer, Some(pixels) if pixels > 0);
+                if init == QOI_FOOTER[0] && footer_expected {
+                    // init and the 7 peeked bytes together need to match all 8 bytes of the footer
                     if let Some(peek) = self.bytes.peek() {
                         if QOI_FOOTER[1..] == peek.map(|elem| *elem) {
                             // we are done, init is the start of the footer
                             // note: this means that this is not a fused iterator
+                            self.at_footer = true;
                             return None;
                         }
                     }
@@ -65,7 +376,7 @@ impl<I: Iterator<Item = u8>> Iterator for QoiChunkDecoder<I> {
                 ))
             } else if short == 0b10 {
                 // luma
-                let next = self.bytes.next()?;
+                let next = self.next_byte()?;
                 Some(QoiChunk::new_luma(
                     (init & 0b00111111) as i8 - 32,
                     ((next >> 4) & 0b00001111) as i8 - 8,
@@ -80,47 +391,606 @@ impl<I: Iterator<Item = u8>> Iterator for QoiChunkDecoder<I> {
     }
 }
 
+/// An iterator over chunks and the byte offsets they start at
+///
+/// Created by [`QoiChunkDecoder::with_offsets`]
+pub struct WithOffsets<I> {
+    chunks: QoiChunkDecoder<I>,
+    offset: usize,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for WithOffsets<I> {
+    type Item = (usize, QoiChunk);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        let offset = self.offset;
+        self.offset += chunk.encoded_len();
+        Some((offset, chunk))
+    }
+}
+
+/// An iterator over chunks and the bytes they were decoded from
+///
+/// Created by [`QoiChunkDecoder::with_raw_bytes`]
+#[cfg(feature = "alloc")]
+pub struct WithRawBytes<I> {
+    chunks: QoiChunkDecoder<I>,
+}
+
+#[cfg(feature = "alloc")]
+impl<I: Iterator<Item = u8>> Iterator for WithRawBytes<I> {
+    type Item = (QoiChunk, alloc::vec::Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        let raw = self.chunks.raw[..self.chunks.raw_len].to_vec();
+        Some((chunk, raw))
+    }
+}
+
 /// A decoder for decoding a qoi from bytes into pixels
 ///
 /// Note: this does not check that decoded pixel count matches the width * height from the header
 /// If the data does not represent a valid qoi format file you may get fewer or more pixels than expect
+///
+/// Use [`QoiDecoder::new_bounded`] to stop after width * height pixels
+///
+/// The chunks are decoded independent of the channels declared by the header,
+/// so Rgba Chunks in an image declaring [`QoiChannels::Rgb`] still change the alpha channel,
+/// see [`DecoderOptions::warn_channel_mismatch`] to detect this
 pub struct QoiDecoder<I> {
     state: CoderState,
     chunks: QoiChunkDecoder<I>,
+    remaining: Option<u64>,
+    header: QoiHeader,
+    decoded: u64,
+    options: DecoderOptions,
+    /// the index of the previous chunk, if it was an Index Chunk
+    last_index: Option<u8>,
+    /// bit set of the indices written so far
+    written_indices: u64,
+    unwritten_index_count: u64,
+    channel_mismatch: bool,
+    /// whether chunks were skipped by [`DecoderOptions::clamp_to_header`]
+    extra_data: bool,
+    /// the number of header bytes read by this decoder, 0 for a resumed decoder
+    header_len: usize,
+    error: Option<DecodeError>,
 }
 
 impl<I: Iterator<Item = u8>> QoiDecoder<I> {
     #[doc(alias = "load")]
-    pub fn new(mut iter: I) -> Option<(QoiHeader, Self)> {
-        let magic = [iter.next()?, iter.next()?, iter.next()?, iter.next()?];
-
-        if magic != QOI_MAGIC {
-            return None;
-        }
+    pub fn new(iter: I) -> Option<(QoiHeader, Self)> {
+        Self::try_new(iter).ok()
+    }
 
-        let width = u32::from_be_bytes([iter.next()?, iter.next()?, iter.next()?, iter.next()?]);
-        let height = u32::from_be_bytes([iter.next()?, iter.next()?, iter.next()?, iter.next()?]);
-        let channels = match iter.next()? {
-            3 => QoiChannels::Rgb,
-            4 => QoiChannels::Rgba,
-            _ => return None,
-        };
-        let color_space = match iter.next()? {
-            0 => QoiColorSpace::SRgbWithLinearAlpha,
-            1 => QoiColorSpace::AllChannelsLinear,
-            _ => return None,
-        };
+    /// Like [`QoiDecoder::new`], but reports why the header could not be read
+    pub fn try_new(mut iter: I) -> Result<(QoiHeader, Self), DecodeError> {
+        let header = QoiHeader::read_from(iter.by_ref())?;
 
-        Some((
-            QoiHeader::new(width, height, channels, color_space),
+        Ok((
+            header,
             Self {
                 state: CoderState::default(),
                 chunks: QoiChunkDecoder::new(iter),
+                remaining: None,
+                header,
+                decoded: 0,
+                options: DecoderOptions::default(),
+                last_index: None,
+                written_indices: 1 << CoderState::index_slot(&Pixel::ZERO),
+                unwritten_index_count: 0,
+                channel_mismatch: false,
+                extra_data: false,
+                header_len: 14,
+                error: None,
+            },
+        ))
+    }
+
+    /// Continue decoding from a [`QoiDecoder::snapshot`]
+    ///
+    /// `remaining` needs to be the bytes following the [`QoiDecoder::bytes_consumed`] at the time of the snapshot,
+    /// otherwise the decoded pixels are garbage.
+    /// The resumed decoder decodes leniently and counts pixels and bytes from the resume point,
+    /// so [`QoiDecoder::finish`] only succeeds when resuming from the start
+    pub fn resume(header: QoiHeader, state: CoderState, remaining: I) -> Self {
+        Self {
+            state,
+            chunks: QoiChunkDecoder::new(remaining),
+            remaining: None,
+            header,
+            decoded: 0,
+            options: DecoderOptions::default(),
+            last_index: None,
+            // which indices have been written before is unknown
+            written_indices: u64::MAX,
+            unwritten_index_count: 0,
+            channel_mismatch: false,
+            extra_data: false,
+            header_len: 0,
+            error: None,
+        }
+    }
+
+    /// Like [`QoiDecoder::new`], but the decoder stops after the width * height pixels declared by the header
+    ///
+    /// If the data ends early you still get fewer pixels than expected.
+    /// Chunk data resembling the footer is not mistaken for the footer before all pixels have been decoded,
+    /// see [`QoiChunkDecoder::new_from_header_aware`]
+    pub fn new_bounded(iter: I) -> Option<(QoiHeader, Self)> {
+        Self::with_options(iter, DecoderOptions::new().enforce_pixel_count(true)).ok()
+    }
+
+    /// Like [`QoiDecoder::try_new`], but validating the input as configured by `options`
+    pub fn with_options(
+        iter: I,
+        options: DecoderOptions,
+    ) -> Result<(QoiHeader, Self), DecodeError> {
+        let (header, mut decoder) = Self::try_new(iter)?;
+        if options.enforce_pixel_count || options.clamp_to_header {
+            decoder.remaining = Some(header.pixel_count());
+        }
+        if options.enforce_pixel_count {
+            decoder.chunks.pixels_until_footer = Some(header.pixel_count());
+        }
+        decoder.options = options;
+        Ok((header, decoder))
+    }
+
+    /// Decode the whole qoi with [`DecoderOptions::strict`], discarding the pixels
+    ///
+    /// Returns the header if the data is a valid qoi, or the first problem found otherwise
+    pub fn validate(iter: I) -> Result<QoiHeader, DecodeError> {
+        let (header, decoder) = Self::with_options(iter, DecoderOptions::strict())?;
+        decoder.finish()?;
+        Ok(header)
+    }
+
+    /// Like [`QoiDecoder::new`], but `callback` is regularly invoked with `(pixels_done, total)`
+    /// where total is the width * height declared by the header
+    ///
+    /// By default the callback is invoked every [`DEFAULT_PROGRESS_INTERVAL`] pixels
+    /// and once more when the decoder runs out of pixels, see [`WithProgress::interval`] to change this
+    pub fn new_with_progress<F: FnMut(u64, u64)>(
+        iter: I,
+        callback: F,
+    ) -> Option<(QoiHeader, WithProgress<Self, F>)> {
+        let (header, decoder) = Self::new(iter)?;
+        let total = header.pixel_count();
+        Some((
+            header,
+            WithProgress {
+                decoder,
+                callback,
+                done: 0,
+                total,
+                interval: DEFAULT_PROGRESS_INTERVAL,
+                until_report: DEFAULT_PROGRESS_INTERVAL,
+                finished: false,
             },
         ))
     }
 }
 
+impl<'a> QoiDecoder<core::iter::Copied<core::slice::Iter<'a, u8>>> {
+    /// Create a new decoder reading from a byte slice, see [`QoiDecoder::new`]
+    pub fn from_slice(bytes: &'a [u8]) -> Option<(QoiHeader, Self)> {
+        Self::new(bytes.iter().copied())
+    }
+}
+
+impl<I> QoiDecoder<I> {
+    /// Compute the [`PixelDigest`](crate::digest::PixelDigest) of the pixels while decoding, see [`WithDigest`]
+    pub fn with_digest(self) -> WithDigest<Self> {
+        WithDigest::new(self)
+    }
+
+    /// Pair every pixel with its `(x, y)` coordinates, based on the width declared by the header
+    ///
+    /// The coordinates continue from the pixels that have already been decoded
+    pub fn with_coords(self) -> WithCoords<Self> {
+        let width = self.header.width;
+        let (x, y) = if width == 0 {
+            (0, 0)
+        } else {
+            (
+                (self.decoded % width as u64) as u32,
+                (self.decoded / width as u64).try_into().unwrap_or(u32::MAX),
+            )
+        };
+        WithCoords {
+            pixels: self,
+            width,
+            x,
+            y,
+        }
+    }
+
+    /// The header of the image being decoded
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// The number of input bytes belonging to the image read so far, including the 14 header bytes
+    ///
+    /// For a resumed decoder this counts from the resume point, see [`QoiDecoder::resume`]
+    ///
+    /// Once the decoder stopped at the footer, this includes the 8 footer bytes,
+    /// so after decoding an image this is its total size, e.g. to find the start of the next image in a stream.
+    /// A bounded decoder stops after the last pixel without reading the footer, see [`QoiDecoder::new_bounded`]
+    pub fn bytes_consumed(&self) -> usize {
+        let footer = if self.chunks.at_footer {
+            // the first byte of the footer has already been consumed, the rest has been peeked at
+            QOI_FOOTER.len() - 1
+        } else {
+            0
+        };
+        self.header_len + self.chunks.consumed + footer
+    }
+
+    /// Yield runs as a single `(pixel, count)` item instead of `count` individual pixels,
+    /// all other pixels are yielded with a count of 1
+    ///
+    /// This is useful for sinks that can fill a whole span at once, e.g. a framebuffer
+    pub fn decode_run_aware(self) -> RunAware<I> {
+        RunAware { decoder: self }
+    }
+
+    /// Save the coder state, to later continue decoding with [`QoiDecoder::resume`]
+    ///
+    /// Together with [`QoiDecoder::bytes_consumed`] this allows decoding a huge image in multiple steps,
+    /// without keeping the decoder and its input around in between
+    pub fn snapshot(&self) -> CoderState {
+        self.state.clone()
+    }
+
+    /// Whether an Rgba Chunk was decoded so far, even though the header declares [`QoiChannels::Rgb`](crate::types::QoiChannels::Rgb)
+    ///
+    /// Only recorded with [`DecoderOptions::warn_channel_mismatch`]
+    pub fn channel_mismatch(&self) -> bool {
+        self.channel_mismatch
+    }
+
+    /// Whether the data contained more pixels than declared by the header, which were skipped
+    ///
+    /// Only recorded with [`DecoderOptions::clamp_to_header`], once all declared pixels have been decoded
+    pub fn extra_data(&self) -> bool {
+        self.extra_data
+    }
+
+    /// The number of Index Chunks so far referring to a never written index
+    ///
+    /// Only counted with [`IndexCheck::Warn`]
+    pub fn unwritten_index_count(&self) -> u64 {
+        self.unwritten_index_count
+    }
+
+    /// The number of pixels still to be produced according to the header, without decoding,
+    /// or `None` if the decoder is not bounded by the header
+    ///
+    /// It is not checked that the data actually contains that many pixels,
+    /// use [`Iterator::count`] to decode and count them.
+    /// For the same reason the decoder implements neither a constant time [`Iterator::count`] nor [`ExactSizeIterator`],
+    /// truncated data yields fewer pixels than declared, so the header only provides the upper bound of [`Iterator::size_hint`]
+    pub fn remaining_declared(&self) -> Option<u64> {
+        self.remaining
+    }
+
+    /// Split the decoder into the header and the decoder producing the remaining pixels
+    pub fn into_parts(self) -> (QoiHeader, Self) {
+        (self.header, self)
+    }
+}
+
+impl<I> QoiDecoder<I>
+where
+    QoiChunkDecoder<I>: Iterator<Item = QoiChunk>,
+{
+    /// Encode the remaining pixels again using the header of this decoder
+    ///
+    /// For a decoder that has not decoded any pixels yet this produces the canonical qoi for the image,
+    /// which can be compared to the original input or decoded again
+    pub fn reencode(self) -> QoiEncoder<Self> {
+        QoiEncoder::new(self.header, self)
+    }
+
+    /// Get the next chunk, validating it as configured by the options
+    fn next_chunk(&mut self) -> Option<QoiChunk> {
+        // unlike the chunk decoder, don't continue decoding the footer as chunks once it has been reached
+        if self.error.is_some() || self.chunks.at_footer {
+            return None;
+        }
+
+        let chunk = self.chunks.next()?;
+
+        match chunk {
+            QoiChunk::Index { idx, .. }
+                if self.options.reject_redundant_index && self.last_index == Some(idx) =>
+            {
+                self.error = Some(DecodeError::RedundantIndex(idx));
+                return None;
+            }
+            QoiChunk::Index { idx, .. } if self.written_indices & (1 << idx) == 0 => {
+                match self.options.unwritten_index {
+                    IndexCheck::Off => {}
+                    IndexCheck::Warn => self.unwritten_index_count += 1,
+                    IndexCheck::Error => {
+                        self.error = Some(DecodeError::UnwrittenIndex(idx));
+                        return None;
+                    }
+                }
+            }
+            QoiChunk::Rgba { .. }
+                if self.options.warn_channel_mismatch
+                    && self.header.channels == QoiChannels::Rgb =>
+            {
+                self.channel_mismatch = true;
+            }
+            QoiChunk::Run { run, .. } if self.options.reject_bad_run => {
+                let remaining = self.header.pixel_count().saturating_sub(self.decoded);
+                if run as u64 > remaining {
+                    self.error = Some(DecodeError::RunExceedsImage { run, remaining });
+                    return None;
+                }
+            }
+            _ => {}
+        }
+
+        self.last_index = match chunk {
+            QoiChunk::Index { idx, .. } => Some(idx),
+            _ => None,
+        };
+
+        Some(chunk)
+    }
+
+    /// Whether all pixels declared by the header have been produced, when the decoder is bounded by the header
+    ///
+    /// With [`DecoderOptions::clamp_to_header`] the chunks up to the footer are skipped once this happens
+    fn out_of_pixels(&mut self) -> bool {
+        if self.remaining != Some(0) {
+            return false;
+        }
+
+        if self.options.clamp_to_header && !self.options.enforce_pixel_count {
+            if self.state.run > 0 {
+                self.state.run = 0;
+                self.extra_data = true;
+            }
+            while self.decode_next_chunk().is_some() {
+                self.state.run = 0;
+                self.extra_data = true;
+            }
+        }
+
+        true
+    }
+
+    /// Decode the next chunk, keeping track of the written indices
+    fn decode_next_chunk(&mut self) -> Option<Pixel> {
+        let chunk = self.next_chunk()?;
+        let pixel = decode_chunk(&mut self.state, chunk);
+        self.written_indices |= 1 << CoderState::index_slot(&pixel);
+
+        if self.options.reject_alpha_in_rgb
+            && self.header.channels == QoiChannels::Rgb
+            && pixel.a != 255
+        {
+            self.error = Some(DecodeError::AlphaInRgbImage { alpha: pixel.a });
+            return None;
+        }
+
+        Some(pixel)
+    }
+}
+
+impl<I: Iterator<Item = u8>> QoiDecoder<I> {
+    /// Decode the remaining pixels and check that the total number of decoded pixels matches the header
+    ///
+    /// This allows decoding leniently, while still being able to tell whether the image was complete.
+    /// Violations of the [`DecoderOptions`] are reported here as well
+    pub fn finish(mut self) -> Result<(), DecodeError> {
+        self.by_ref().for_each(drop);
+
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        let expected = self.header.pixel_count();
+        if self.decoded != expected {
+            return Err(DecodeError::PixelCountMismatch {
+                expected,
+                found: self.decoded,
+            });
+        }
+
+        if self.options.require_footer && self.trailing_bytes().is_none() {
+            return Err(DecodeError::MissingFooter);
+        }
+
+        Ok(())
+    }
+
+    /// Decode the `width` * `height` pixels of the rectangle starting at `(x, y)`, row by row
+    ///
+    /// The region always contains exactly `width` * `height` pixels,
+    /// pixels inside the image come from the decoded data, while pixels outside of it are `fill`,
+    /// e.g. for a tile at the edge of an image that is not a multiple of the tile size.
+    /// Pixels missing as the data ends early are `fill` as well.
+    ///
+    /// Decoding stops after the last row of the region.
+    /// Returns `None` without decoding if the region is too large to be held in memory
+    #[cfg(feature = "alloc")]
+    pub fn decode_region(
+        self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        fill: Pixel,
+    ) -> Option<alloc::vec::Vec<Pixel>> {
+        let len = usize::try_from(width as u64 * height as u64).ok()?;
+        // a Vec can hold at most isize::MAX bytes
+        let bytes = len.checked_mul(core::mem::size_of::<Pixel>())?;
+        if bytes > isize::MAX as usize {
+            return None;
+        }
+
+        let mut region = alloc::vec![fill; len];
+        let (end_x, end_y) = (x as u64 + width as u64, y as u64 + height as u64);
+
+        for ((px, py), pixel) in self
+            .with_coords()
+            .take_while(|((_, py), _)| (*py as u64) < end_y)
+        {
+            if px >= x && (px as u64) < end_x && py >= y {
+                region[(py - y) as usize * width as usize + (px - x) as usize] = pixel;
+            }
+        }
+
+        Some(region)
+    }
+
+    /// Decode the remaining pixels into `sink`, checking `cancel` every [`CANCEL_CHECK_INTERVAL`] pixels
+    ///
+    /// Once `cancel` is set, e.g. by another thread as the image is no longer needed, this returns [`DecodeError::Cancelled`].
+    /// Otherwise this returns `Ok` when the decoder runs out of pixels, use [`QoiDecoder::finish`] to validate the image
+    pub fn decode_cancellable(
+        &mut self,
+        cancel: &AtomicBool,
+        mut sink: impl FnMut(Pixel),
+    ) -> Result<(), DecodeError> {
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(DecodeError::Cancelled);
+            }
+
+            let mut decoded = 0;
+            for pixel in self.by_ref().take(CANCEL_CHECK_INTERVAL) {
+                sink(pixel);
+                decoded += 1;
+            }
+            if decoded < CANCEL_CHECK_INTERVAL {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Decode pixels into `out` as 4 bytes rgba each, until `out` is full or the decoder runs out of pixels
+    ///
+    /// Returns the number of pixels written, a trailing part of `out` shorter than 4 bytes stays untouched
+    pub fn decode_rgba_into(&mut self, out: &mut [u8]) -> usize {
+        self.decode_into(out, |pixel| pixel)
+    }
+
+    /// Like [`QoiDecoder::decode_rgba_into`], but the pixels are written with premultiplied alpha,
+    /// see [`Pixel::to_premultiplied`] for the rounding
+    ///
+    /// This is the format many GPU textures expect, fully transparent pixels are written as all zero
+    pub fn decode_rgba_premultiplied_into(&mut self, out: &mut [u8]) -> usize {
+        self.decode_into(out, |pixel| pixel.to_premultiplied())
+    }
+
+    fn decode_into(&mut self, out: &mut [u8], mut convert: impl FnMut(Pixel) -> Pixel) -> usize {
+        let mut written = 0;
+        for (dest, pixel) in out.chunks_exact_mut(4).zip(self.by_ref()) {
+            let pixel = convert(pixel);
+            dest.copy_from_slice(&[pixel.r, pixel.g, pixel.b, pixel.a]);
+            written += 1;
+        }
+        written
+    }
+
+    /// Check the footer following the decoded pixels and return the bytes after it
+    ///
+    /// Some tools append metadata after the footer, this gives access to it once decoding is done.
+    /// Returns `None` if the next bytes are not the footer, e.g. as not all pixels have been decoded yet
+    pub fn trailing_bytes(self) -> Option<TrailingBytes<I>> {
+        let mut bytes = self.chunks.bytes;
+
+        if self.chunks.at_footer {
+            // the first byte of the footer has already been consumed, the rest has been peeked at
+            bytes.by_ref().take(QOI_FOOTER.len() - 1).for_each(drop);
+        } else {
+            // e.g. a bounded decoder stops before reading the footer
+            let mut footer = bytes.by_ref().take(QOI_FOOTER.len());
+            if !QOI_FOOTER.iter().all(|&byte| footer.next() == Some(byte)) {
+                return None;
+            }
+        }
+
+        Some(TrailingBytes { bytes })
+    }
+}
+
+/// The bytes following the footer of a qoi file
+///
+/// Created by [`QoiDecoder::trailing_bytes`]
+pub struct TrailingBytes<I> {
+    bytes: PeekN<7, I, u8>,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for TrailingBytes<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.bytes.next()
+    }
+}
+
+/// Decode the pixel represented by a chunk and update the coder state accordingly
+///
+/// For a run chunk this returns the first pixel of the run and
+/// stores the remaining run length in the coder state
+fn decode_chunk(state: &mut CoderState, chunk: QoiChunk) -> Pixel {
+    match chunk {
+        QoiChunk::Rgb { r, g, b, .. } => {
+            let next = Pixel {
+                r,
+                g,
+                b,
+                a: state.previous.a,
+            };
+            state.previous = next;
+            state.update_index(next);
+            next
+        }
+        QoiChunk::Rgba { r, g, b, a, .. } => {
+            let next = Pixel { r, g, b, a };
+            state.previous = next;
+            state.update_index(next);
+            next
+        }
+        QoiChunk::Index { idx, .. } => {
+            let next = state.index[idx as usize];
+            state.previous = next;
+            next
+        }
+        QoiChunk::Diff { dr, dg, db, .. } => {
+            let next = state.previous.apply_diff(dr, dg, db);
+            state.previous = next;
+            state.update_index(next);
+            next
+        }
+        QoiChunk::Luma {
+            dg, dr_dg, db_dg, ..
+        } => {
+            let next = state.previous.apply_luma(dg, dr_dg, db_dg);
+            state.previous = next;
+            state.update_index(next);
+            next
+        }
+        QoiChunk::Run { run, .. } => {
+            let next = state.previous;
+            state.run = run.saturating_sub(1);
+            state.update_index(next);
+            next
+        }
+    }
+}
+
 impl<I> Iterator for QoiDecoder<I>
 where
     QoiChunkDecoder<I>: Iterator<Item = QoiChunk>,
@@ -128,66 +998,340 @@ where
     type Item = Pixel;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.state.run > 0 {
+        if self.out_of_pixels() {
+            return None;
+        }
+
+        let next = if self.state.run > 0 {
             self.state.run -= 1;
-            Some(self.state.previous.clone())
+            self.state.previous
         } else {
-            let chunk = self.chunks.next()?;
-
-            match chunk {
-                QoiChunk::Rgb { r, g, b, .. } => {
-                    let next = Pixel {
-                        r,
-                        g,
-                        b,
-                        a: self.state.previous.a,
-                    };
-                    self.state.previous = next.clone();
-                    self.state.index[next.pixel_hash() as usize] = next.clone();
-                    Some(next)
-                }
-                QoiChunk::Rgba { r, g, b, a, .. } => {
-                    let next = Pixel { r, g, b, a };
-                    self.state.previous = next.clone();
-                    self.state.index[next.pixel_hash() as usize] = next.clone();
-                    Some(next)
+            self.decode_next_chunk()?
+        };
+
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        self.decoded += 1;
+
+        Some(next)
+    }
+
+    /// Skips runs as a whole instead of emitting each of their pixels
+    ///
+    /// Note: chunks are still decoded one after another,
+    /// as each chunk depends on the coder state left behind by the previous ones
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        while n > 0 {
+            if self.out_of_pixels() {
+                return None;
+            }
+
+            let skipped = if self.state.run > 0 {
+                let mut skip = n.min(self.state.run as usize) as u64;
+                if let Some(remaining) = self.remaining {
+                    skip = skip.min(remaining);
                 }
-                QoiChunk::Index { idx, .. } => {
-                    let next = self.state.index[idx as usize].clone();
-                    self.state.previous = next.clone();
-                    Some(next)
+                self.state.run -= skip as u8;
+                skip
+            } else {
+                self.decode_next_chunk()?;
+                1
+            };
+
+            if let Some(remaining) = &mut self.remaining {
+                *remaining -= skipped;
+            }
+            self.decoded += skipped;
+            n -= skipped as usize;
+        }
+
+        self.next()
+    }
+
+    /// Expands runs in a tight loop instead of re-entering [`Iterator::next`] for each of their pixels
+    fn fold<B, F>(mut self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, Self::Item) -> B,
+    {
+        let mut acc = init;
+        loop {
+            if self.out_of_pixels() {
+                return acc;
+            }
+
+            if self.state.run > 0 {
+                let mut run = self.state.run as u64;
+                if let Some(remaining) = self.remaining {
+                    run = run.min(remaining);
                 }
-                QoiChunk::Diff { dr, dg, db, .. } => {
-                    let next = Pixel {
-                        r: self.state.previous.r.wrapping_add_signed(dr),
-                        g: self.state.previous.g.wrapping_add_signed(dg),
-                        b: self.state.previous.b.wrapping_add_signed(db),
-                        a: self.state.previous.a,
-                    };
-                    self.state.previous = next.clone();
-                    self.state.index[next.pixel_hash() as usize] = next.clone();
-                    Some(next)
+                let pixel = self.state.previous;
+                for _ in 0..run {
+                    acc = f(acc, pixel);
                 }
-                QoiChunk::Luma {
-                    dg, dr_dg, db_dg, ..
-                } => {
-                    let next = Pixel {
-                        r: self.state.previous.r.wrapping_add_signed(dr_dg + dg),
-                        g: self.state.previous.g.wrapping_add_signed(dg),
-                        b: self.state.previous.b.wrapping_add_signed(db_dg + dg),
-                        a: self.state.previous.a,
-                    };
-                    self.state.previous = next.clone();
-                    self.state.index[next.pixel_hash() as usize] = next.clone();
-                    Some(next)
+                self.state.run -= run as u8;
+                if let Some(remaining) = &mut self.remaining {
+                    *remaining -= run;
                 }
-                QoiChunk::Run { run, .. } => {
-                    let next = self.state.previous.clone();
-                    self.state.run = run - 1;
-                    self.state.index[next.pixel_hash() as usize] = next.clone();
-                    Some(next)
+                self.decoded += run;
+            } else {
+                let Some(pixel) = self.decode_next_chunk() else {
+                    return acc;
+                };
+                if let Some(remaining) = &mut self.remaining {
+                    *remaining -= 1;
                 }
+                self.decoded += 1;
+                acc = f(acc, pixel);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (0, usize::try_from(remaining).ok()),
+            None => (0, None),
+        }
+    }
+}
+
+/// An iterator pairing pixels with their `(x, y)` coordinates
+///
+/// Created by [`QoiDecoder::with_coords`]
+pub struct WithCoords<D> {
+    pixels: D,
+    width: u32,
+    x: u32,
+    y: u32,
+}
+
+impl<D: Iterator<Item = Pixel>> Iterator for WithCoords<D> {
+    type Item = ((u32, u32), Pixel);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pixel = self.pixels.next()?;
+        let coords = (self.x, self.y);
+
+        self.x += 1;
+        if self.x >= self.width {
+            self.x = 0;
+            self.y = self.y.saturating_add(1);
+        }
+
+        Some((coords, pixel))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pixels.size_hint()
+    }
+}
+
+/// An iterator over pixels and how often they repeat
+///
+/// Created by [`QoiDecoder::decode_run_aware`]
+pub struct RunAware<I> {
+    decoder: QoiDecoder<I>,
+}
+
+impl<I> Iterator for RunAware<I>
+where
+    QoiChunkDecoder<I>: Iterator<Item = QoiChunk>,
+{
+    type Item = (Pixel, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoder = &mut self.decoder;
+        if decoder.out_of_pixels() {
+            return None;
+        }
+
+        let mut count = if decoder.state.run > 0 {
+            // the rest of a run partially consumed before
+            let run = decoder.state.run as u64;
+            decoder.state.run = 0;
+            run
+        } else {
+            decoder.decode_next_chunk()?;
+            let count = 1 + decoder.state.run as u64;
+            decoder.state.run = 0;
+            count
+        };
+
+        if let Some(remaining) = &mut decoder.remaining {
+            let clamped = count.min(*remaining);
+            // keep the rest of a run exceeding the image around, like when decoding pixel by pixel
+            decoder.state.run = (count - clamped) as u8;
+            count = clamped;
+            *remaining -= count;
+        }
+        decoder.decoded += count;
+
+        Some((decoder.state.previous, count as u32))
+    }
+}
+
+/// The number of pixels between two checks of the cancel flag of [`QoiDecoder::decode_cancellable`]
+pub const CANCEL_CHECK_INTERVAL: usize = 4096;
+
+/// The default number of pixels between two invocations of the progress callback of [`WithProgress`]
+pub const DEFAULT_PROGRESS_INTERVAL: u64 = 65536;
+
+/// A decoder reporting its progress to a callback
+///
+/// Created by [`QoiDecoder::new_with_progress`]
+pub struct WithProgress<D, F> {
+    decoder: D,
+    callback: F,
+    done: u64,
+    total: u64,
+    interval: u64,
+    until_report: u64,
+    finished: bool,
+}
+
+impl<D, F> WithProgress<D, F> {
+    /// Invoke the callback every `interval` pixels instead of every [`DEFAULT_PROGRESS_INTERVAL`] pixels
+    ///
+    /// An interval of 0 is treated as 1
+    pub fn interval(mut self, interval: u64) -> Self {
+        self.interval = interval.max(1);
+        self.until_report = self.interval;
+        self
+    }
+}
+
+impl<D, F> Iterator for WithProgress<D, F>
+where
+    D: Iterator<Item = Pixel>,
+    F: FnMut(u64, u64),
+{
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(next) = self.decoder.next() else {
+            if !self.finished {
+                self.finished = true;
+                (self.callback)(self.done, self.total);
+            }
+            return None;
+        };
+
+        self.done += 1;
+        self.until_report -= 1;
+        if self.until_report == 0 {
+            self.until_report = self.interval;
+            (self.callback)(self.done, self.total);
+        }
+
+        Some(next)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.decoder.size_hint()
+    }
+}
+
+/// A decoder to which the bytes of a qoi are fed in arbitrary slices, instead of pulling them from an iterator
+///
+/// Pixels become available through the [`Iterator`] implementation as soon as enough bytes have been fed.
+/// Note: this is not a fused iterator, once more bytes have been fed more pixels may be available
+#[cfg(feature = "alloc")]
+pub struct PushDecoder {
+    buffer: alloc::collections::VecDeque<u8>,
+    header: Option<QoiHeader>,
+    state: CoderState,
+    finished: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl PushDecoder {
+    pub fn new() -> Self {
+        Self {
+            buffer: alloc::collections::VecDeque::new(),
+            header: None,
+            state: CoderState::default(),
+            finished: false,
+        }
+    }
+
+    /// Feed the next bytes of the qoi to the decoder
+    ///
+    /// Fails if the header is invalid, the header is parsed as soon as all of its 14 bytes are available
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), DecodeError> {
+        if self.finished {
+            return Ok(());
+        }
+
+        self.buffer.extend(bytes);
+
+        if self.header.is_none() && self.buffer.len() >= 14 {
+            let (header, _) = QoiDecoder::try_new(self.buffer.drain(..14))?;
+            self.header = Some(header);
+        }
+
+        Ok(())
+    }
+
+    /// The header, if enough bytes have been fed to parse it
+    pub fn header(&self) -> Option<&QoiHeader> {
+        self.header.as_ref()
+    }
+
+    /// Whether the footer has been reached
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for PushDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Iterator for PushDecoder {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state.run > 0 {
+            self.state.run -= 1;
+            return Some(self.state.previous);
+        }
+
+        if self.header.is_none() || self.finished {
+            return None;
+        }
+
+        let init = *self.buffer.front()?;
+
+        if init == QOI_FOOTER[0] {
+            // this might be the start of the footer,
+            // so we need to wait until we can tell them apart
+            if self.buffer.len() < QOI_FOOTER.len() {
+                return None;
+            }
+
+            if self.buffer.range(..QOI_FOOTER.len()).eq(QOI_FOOTER.iter()) {
+                self.buffer.drain(..QOI_FOOTER.len());
+                self.finished = true;
+                return None;
             }
         }
+
+        let chunk_len = match init {
+            0b11111111 => 5,
+            0b11111110 => 4,
+            _ if init >> 6 == 0b10 => 2,
+            _ => 1,
+        };
+
+        if self.buffer.len() < chunk_len {
+            return None;
+        }
+
+        let chunk = QoiChunkDecoder::new(self.buffer.drain(..chunk_len)).next()?;
+        Some(decode_chunk(&mut self.state, chunk))
     }
 }
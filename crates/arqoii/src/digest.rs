@@ -0,0 +1,97 @@
+use core::iter::FusedIterator;
+
+use crate::types::Pixel;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// A stable 64-bit FNV-1a hash over the RGBA bytes of pixels
+///
+/// The digest only depends on the pixels, so two different encodings of the same image have the same digest.
+/// Unlike [`core::hash::Hasher`] implementations of std the result is stable across platforms and versions
+#[derive(Debug, Clone)]
+pub struct PixelDigest {
+    state: u64,
+}
+
+impl PixelDigest {
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    /// Feed the next pixel into the digest
+    pub fn update(&mut self, pixel: &Pixel) {
+        for byte in [pixel.r, pixel.g, pixel.b, pixel.a] {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// The digest of all pixels fed so far
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Default for PixelDigest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compute the [`PixelDigest`] of the pixels, e.g. of a [`QoiDecoder`](crate::decode::QoiDecoder)
+pub fn pixel_digest<I: IntoIterator<Item = Pixel>>(pixels: I) -> u64 {
+    let mut digest = PixelDigest::new();
+    for pixel in pixels {
+        digest.update(&pixel);
+    }
+    digest.finish()
+}
+
+/// An iterator passing pixels through unchanged, while computing their [`PixelDigest`]
+///
+/// Iterate it by reference and read [`WithDigest::digest`] afterwards,
+/// this avoids buffering all pixels just to hash them.
+///
+/// Created by [`QoiDecoder::with_digest`](crate::decode::QoiDecoder::with_digest)
+pub struct WithDigest<I> {
+    pixels: I,
+    digest: PixelDigest,
+}
+
+impl<I> WithDigest<I> {
+    pub(crate) fn new(pixels: I) -> Self {
+        Self {
+            pixels,
+            digest: PixelDigest::new(),
+        }
+    }
+
+    /// The digest of the pixels produced so far
+    pub fn digest(&self) -> u64 {
+        self.digest.finish()
+    }
+}
+
+impl<I: Iterator<Item = Pixel>> Iterator for WithDigest<I> {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pixel = self.pixels.next()?;
+        self.digest.update(&pixel);
+        Some(pixel)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pixels.size_hint()
+    }
+}
+
+impl<I> FusedIterator for WithDigest<I>
+where
+    WithDigest<I>: Iterator,
+    I: FusedIterator,
+{
+}
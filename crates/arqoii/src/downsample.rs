@@ -0,0 +1,68 @@
+use alloc::vec::Vec;
+
+use crate::types::Pixel;
+
+/// The reasons a lossless downsample can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownsampleError {
+    /// The width or height is odd, so the image can't be split into 2x2 blocks
+    OddSize { width: u32, height: u32 },
+    /// The 2x2 block at `(x, y)` of the downsampled image contains more than one color
+    NonUniformBlock { x: u32, y: u32 },
+}
+
+impl core::fmt::Display for DownsampleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DownsampleError::OddSize { width, height } => {
+                write!(f, "{width}x{height} can't be split into 2x2 blocks")
+            }
+            DownsampleError::NonUniformBlock { x, y } => {
+                write!(f, "the 2x2 block at ({x}, {y}) is not uniform")
+            }
+        }
+    }
+}
+
+/// Halve the width and height of an image consisting of uniform 2x2 blocks, e.g. a UI asset rendered at 2x
+///
+/// This is lossless, scaling the result up again by repeating each pixel 2x2 gives back the original.
+/// Returns the new width, height and pixels, or the first block (in row order) that is not uniform
+///
+/// # Panics
+/// if `pixels` contains fewer than width * height pixels
+pub fn downsample_2x_lossless(
+    pixels: &[Pixel],
+    width: u32,
+    height: u32,
+) -> Result<(u32, u32, Vec<Pixel>), DownsampleError> {
+    assert!(
+        pixels.len() as u64 >= width as u64 * height as u64,
+        "fewer pixels than width * height"
+    );
+    if width % 2 != 0 || height % 2 != 0 {
+        return Err(DownsampleError::OddSize { width, height });
+    }
+
+    let (half_width, half_height) = (width / 2, height / 2);
+    let mut result = Vec::with_capacity(half_width as usize * half_height as usize);
+
+    let width = width as usize;
+    for y in 0..half_height {
+        let top = y as usize * 2 * width;
+        let bottom = top + width;
+        for x in 0..half_width {
+            let left = x as usize * 2;
+            let pixel = pixels[top + left];
+            let uniform = pixels[top + left + 1] == pixel
+                && pixels[bottom + left] == pixel
+                && pixels[bottom + left + 1] == pixel;
+            if !uniform {
+                return Err(DownsampleError::NonUniformBlock { x, y });
+            }
+            result.push(pixel);
+        }
+    }
+
+    Ok((half_width, half_height, result))
+}
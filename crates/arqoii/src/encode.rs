@@ -2,29 +2,158 @@ use core::iter::FusedIterator;
 
 use arqoii_types::QOI_FOOTER;
 
-use crate::types::{CoderState, Pixel, QoiChunk, QoiHeader};
+use crate::iterator_helper::ErrorShunt;
+use crate::stats::WithStats;
+use crate::types::{ChunkBuf, CoderState, Pixel, QoiChannels, QoiChunk, QoiColorSpace, QoiHeader};
+
+/// Which chunk to choose if a pixel can be encoded both as an Index and a Diff Chunk
+///
+/// Both are a single byte, so this only changes which valid encoding is produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChunkPreference {
+    /// Prefer the Index Chunk, like the reference encoder
+    #[default]
+    IndexFirst,
+    /// Prefer the Diff Chunk
+    DiffFirst,
+}
+
+/// Options tweaking the output of the encoder
+///
+/// The default options produce the same output as the reference encoder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncoderOptions {
+    max_run: u8,
+    footer: bool,
+    strict_channels: bool,
+    prefer: ChunkPreference,
+}
+
+impl EncoderOptions {
+    pub fn new() -> Self {
+        Self {
+            max_run: QoiChunk::MAX_RUN,
+            footer: true,
+            strict_channels: false,
+            prefer: ChunkPreference::IndexFirst,
+        }
+    }
+
+    /// Split runs after at most `max_run` pixels instead of 62, clamped to the range 1..=62
+    ///
+    /// The output stays valid qoi, this is useful for testing decoders that mishandle long runs
+    pub fn max_run(mut self, max_run: u8) -> Self {
+        self.max_run = max_run.clamp(1, QoiChunk::MAX_RUN);
+        self
+    }
+
+    /// Whether to end the file with the footer, enabled by default
+    ///
+    /// see [`QoiEncoder::new_no_footer`] for when to omit it
+    pub fn footer(mut self, footer: bool) -> Self {
+        self.footer = footer;
+        self
+    }
+
+    /// Force the alpha channel of every pixel to 255 if the header declares [`QoiChannels::Rgb`], disabled by default
+    ///
+    /// This guarantees the output matches the channel declaration of the header, at the cost of dropping alpha.
+    /// A [`QoiChunkEncoder`] does not know the header, pass the channels via [`QoiChunkEncoder::channels`]
+    /// for this to take effect there
+    pub fn strict_channels(mut self, strict_channels: bool) -> Self {
+        self.strict_channels = strict_channels;
+        self
+    }
+
+    /// Choose between an Index and a Diff Chunk when both are possible, see [`ChunkPreference`]
+    pub fn prefer(mut self, prefer: ChunkPreference) -> Self {
+        self.prefer = prefer;
+        self
+    }
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// An encoder for encoding Pixels into Chunks
 pub struct QoiChunkEncoder<I> {
     state: CoderState,
     pixel: I,
     peek: Option<Pixel>,
+    /// the chunk buffered by [`QoiChunkEncoder::peek_chunk`], `Some(None)` if the encoder ran out of pixels
+    peeked_chunk: Option<Option<QoiChunk>>,
+    options: EncoderOptions,
+    /// force the alpha channel of all pixels to 255
+    opaque: bool,
 }
 
 impl<I> QoiChunkEncoder<I> {
     pub fn new(pixel: I) -> Self {
+        Self::with_options(pixel, EncoderOptions::default())
+    }
+
+    /// Create a new encoder using the provided options
+    pub fn with_options(pixel: I, options: EncoderOptions) -> Self {
         Self {
             state: CoderState::default(),
             pixel,
             peek: None,
+            peeked_chunk: None,
+            options,
+            opaque: false,
         }
     }
+
+    /// Create a new encoder starting with `previous` as the previous pixel instead of `(0, 0, 0, 255)`
+    ///
+    /// The index still starts out zeroed,
+    /// this is useful when continuing an image at a point where only the previous pixel is known
+    pub fn with_initial(previous: Pixel, pixel: I) -> Self {
+        Self::with_initial_and_options(previous, pixel, EncoderOptions::default())
+    }
+
+    /// Like [`QoiChunkEncoder::with_initial`], but using the provided options
+    ///
+    /// Use the same options as for the preceding part of the image,
+    /// e.g. when encoding an image in stripes
+    pub fn with_initial_and_options(previous: Pixel, pixel: I, options: EncoderOptions) -> Self {
+        Self {
+            state: CoderState {
+                previous,
+                ..CoderState::default()
+            },
+            ..Self::with_options(pixel, options)
+        }
+    }
+
+    /// The channels declared by the header of the image being encoded,
+    /// only used by [`EncoderOptions::strict_channels`]
+    pub fn channels(mut self, channels: QoiChannels) -> Self {
+        self.opaque = self.options.strict_channels && channels == QoiChannels::Rgb;
+        self
+    }
+
+    /// Count the produced chunks, see [`WithStats`]
+    pub fn with_stats(self) -> WithStats<Self> {
+        WithStats::new(self)
+    }
 }
 
-impl<I: Iterator<Item = Pixel>> Iterator for QoiChunkEncoder<I> {
-    type Item = QoiChunk;
+impl<I: Iterator<Item = Pixel>> QoiChunkEncoder<I> {
+    /// Look at the next chunk without consuming it
+    ///
+    /// The chunk is buffered and returned by the following call to [`Iterator::next`]
+    pub fn peek_chunk(&mut self) -> Option<&QoiChunk> {
+        if self.peeked_chunk.is_none() {
+            self.peeked_chunk = Some(self.encode_next());
+        }
+        self.peeked_chunk.as_ref().and_then(Option::as_ref)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn encode_next(&mut self) -> Option<QoiChunk> {
         // we try to encode using these priorities:
         // - fewest bytes
         // - simplest: previous_pixel > index lookup > calculation
@@ -40,7 +169,13 @@ impl<I: Iterator<Item = Pixel>> Iterator for QoiChunkEncoder<I> {
         // 6. Rgba   5-bytes / pixel
 
         let pixel = loop {
-            let Some(pixel) = self.peek.take().or_else(|| self.pixel.next()) else {
+            let next = self.peek.take().or_else(|| self.pixel.next());
+            let next = if self.opaque {
+                next.map(|pixel| Pixel { a: 255, ..pixel })
+            } else {
+                next
+            };
+            let Some(pixel) = next else {
                 // end of input pixels
                 // check if we have an in progress run
                 return if self.state.run > 0 {
@@ -54,21 +189,22 @@ impl<I: Iterator<Item = Pixel>> Iterator for QoiChunkEncoder<I> {
 
             if pixel == self.state.previous {
                 self.state.run += 1;
-                if self.state.run == 62 {
-                    // reached max run write return it and rest run
-                    self.state.run = 0;
-                    // we don't need to update the index or the previous pixel as we are on a run
-                    // and as such the pixel preceding the run has already set both correctly
-                    return Some(QoiChunk::new_run(62));
-                }
 
                 if self.state.run == 1 {
                     // if the first image pixel is r: 0, g: 0, b: 0, a:255
                     // we will still need to update the index as the index is 0 initialized
 
-                    let idx = pixel.pixel_hash();
-                    self.state.index[idx as usize] = pixel;
+                    self.state.update_index(pixel);
                 }
+
+                if self.state.run == self.options.max_run {
+                    // reached max run write return it and rest run
+                    self.state.run = 0;
+                    // we don't need to update the index or the previous pixel as we are on a run
+                    // and as such the pixel preceding the run has already set both correctly
+                    return Some(QoiChunk::new_run(self.options.max_run));
+                }
+
                 // updated the we don't know if this the end of a run yet,
                 // we only know that on the pixel after the run or on the end of the pixels
                 continue;
@@ -86,64 +222,219 @@ impl<I: Iterator<Item = Pixel>> Iterator for QoiChunkEncoder<I> {
             return Some(next);
         }
 
-        let idx = pixel.pixel_hash();
+        Some(encode_pixel(&mut self.state, pixel, self.options.prefer))
+    }
+}
 
-        let chunk = if self.state.index[idx as usize] == pixel {
-            // we can't use a run so we won't violate the standard which states:
-            // > A valid encoder must not issue 2 or more consecutive QOI_OP_INDEX
-            // > chunks to the same index. QOI_OP_RUN should be used instead.
+impl<I: Iterator<Item = Pixel>> Iterator for QoiChunkEncoder<I> {
+    type Item = QoiChunk;
 
-            // we have a matching index so use that
-            QoiChunk::new_index(idx)
-        } else if pixel.a == self.state.previous.a {
-            // old_{r,g,b} + d{r,g,b} = new_{r,g,b}
-            // d{r,g,b} = new_{r,g,b} - old_{r,g,b}
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked_chunk.take() {
+            Some(chunk) => chunk,
+            None => self.encode_next(),
+        }
+    }
+}
 
-            let dr = pixel.r.wrapping_sub(self.state.previous.r) as i8;
-            let dg = pixel.g.wrapping_sub(self.state.previous.g) as i8;
-            let db = pixel.b.wrapping_sub(self.state.previous.b) as i8;
+impl<I> FusedIterator for QoiChunkEncoder<I>
+where
+    QoiChunkEncoder<I>: Iterator,
+    I: FusedIterator,
+{
+}
+
+/// Encode a pixel that does not continue a run and update the coder state accordingly
+fn encode_pixel(state: &mut CoderState, pixel: Pixel, prefer: ChunkPreference) -> QoiChunk {
+    let idx = CoderState::index_slot(&pixel);
+
+    // old_{r,g,b} + d{r,g,b} = new_{r,g,b}
+    // d{r,g,b} = new_{r,g,b} - old_{r,g,b}
+    let dr = pixel.r.wrapping_sub(state.previous.r) as i8;
+    let dg = pixel.g.wrapping_sub(state.previous.g) as i8;
+    let db = pixel.b.wrapping_sub(state.previous.b) as i8;
+    let diff_possible = pixel.a == state.previous.a
+        && QoiChunk::DIFF_RANGE.contains(&dr)
+        && QoiChunk::DIFF_RANGE.contains(&dg)
+        && QoiChunk::DIFF_RANGE.contains(&db);
+
+    let chunk = if prefer == ChunkPreference::DiffFirst && diff_possible {
+        QoiChunk::new_diff(dr, dg, db)
+    } else if state.index[idx as usize] == pixel {
+        // we can't use a run so we won't violate the standard which states:
+        // > A valid encoder must not issue 2 or more consecutive QOI_OP_INDEX
+        // > chunks to the same index. QOI_OP_RUN should be used instead.
+
+        // we have a matching index so use that
+        QoiChunk::new_index(idx)
+    } else if pixel.a == state.previous.a {
+        if diff_possible {
+            // we can encode it as a diff op so use that
+            QoiChunk::new_diff(dr, dg, db)
+        } else {
+            let dr_dg = dr.wrapping_sub(dg);
+            let db_dg = db.wrapping_sub(dg);
 
-            if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
-                // we can encode it as a diff op so use that
-                QoiChunk::new_diff(dr, dg, db)
+            if QoiChunk::LUMA_DG_RANGE.contains(&dg)
+                && QoiChunk::LUMA_DR_DG_RANGE.contains(&dr_dg)
+                && QoiChunk::LUMA_DR_DG_RANGE.contains(&db_dg)
+            {
+                // luma encoding is possible so use that
+                QoiChunk::new_luma(dg, dr_dg, db_dg)
             } else {
-                let dr_dg = dr.wrapping_sub(dg);
-                let db_dg = db.wrapping_sub(dg);
-
-                if (-32..=31).contains(&dg)
-                    && (-8..=7).contains(&dr_dg)
-                    && (-8..=7).contains(&db_dg)
-                {
-                    // luma encoding is possible so use that
-                    QoiChunk::new_luma(dg, dr_dg, db_dg)
-                } else {
-                    // fallback to rgb as we already checked that alpha matches
-                    QoiChunk::new_rgb(pixel.r, pixel.g, pixel.b)
-                }
+                // fallback to rgb as we already checked that alpha matches
+                QoiChunk::new_rgb(pixel.r, pixel.g, pixel.b)
             }
-        } else {
-            // no run, no index match and different alpha, so we need to fallback to rgba
-            QoiChunk::new_rgba(pixel.r, pixel.g, pixel.b, pixel.a)
-        };
+        }
+    } else {
+        // no run, no index match and different alpha, so we need to fallback to rgba
+        QoiChunk::new_rgba(pixel.r, pixel.g, pixel.b, pixel.a)
+    };
 
-        self.state.index[idx as usize] = pixel.clone();
-        self.state.previous = pixel;
-        Some(chunk)
-    }
+    state.update_index(pixel);
+    state.previous = pixel;
+    chunk
 }
 
-impl<I> FusedIterator for QoiChunkEncoder<I>
+/// Turn chunks into their bytes
+///
+/// This only produces the chunk data, neither header nor footer are included
+pub fn chunks_to_bytes<C>(chunks: C) -> core::iter::Flatten<C::IntoIter>
 where
-    QoiChunkEncoder<I>: Iterator,
-    I: FusedIterator,
+    C: IntoIterator<Item = QoiChunk>,
+{
+    chunks.into_iter().flatten()
+}
+
+/// Calculate the size of the qoi file the pixels would be encoded into, including header and footer
+///
+/// This runs the same chunk selection as [`QoiEncoder::new`] for a header declaring `channels`,
+/// without producing any bytes. Just like the encoder the alpha channel is kept as given,
+/// even for [`QoiChannels::Rgb`]
+pub fn estimate_qoi_size<I>(pixels: I, channels: QoiChannels) -> usize
+where
+    I: IntoIterator<Item = Pixel>,
+{
+    let chunks: usize =
+        QoiChunkEncoder::with_options(pixels.into_iter(), EncoderOptions::default())
+            .channels(channels)
+            .map(|chunk| chunk.encoded_len())
+            .sum();
+
+    14 + chunks + QOI_FOOTER.len()
+}
+
+/// The maximum size of a qoi file with the provided header, including header and footer,
+/// see [`QoiHeader::max_file_len`]
+pub fn max_output_len(header: &QoiHeader) -> Option<usize> {
+    header.max_file_len()
+}
+
+/// Encode the image, returning the header bytes separate from the encoder producing the chunk and footer bytes
+///
+/// Together they are the same bytes as produced by [`QoiEncoder::new`],
+/// e.g. for a container storing the header apart from the image data
+pub fn encode_split<I>(header: QoiHeader, pixels: I) -> ([u8; 14], QoiEncoder<I::IntoIter>)
+where
+    I: IntoIterator<Item = Pixel>,
 {
+    let mut encoder = QoiEncoder::new(header, pixels.into_iter());
+    encoder.header_bytes.by_ref().for_each(drop);
+    (header.to_bytes(), encoder)
+}
+
+/// Encode the image into a new Vec, see [`QoiEncoder::new`]
+#[cfg(feature = "alloc")]
+pub fn encode_to_vec<I>(header: QoiHeader, pixels: I) -> alloc::vec::Vec<u8>
+where
+    I: IntoIterator<Item = Pixel>,
+{
+    let mut out = alloc::vec::Vec::new();
+    encode_into_vec(header, pixels, &mut out);
+    out
+}
+
+/// Encode the image into `out`, replacing its previous content
+///
+/// This reuses the allocation of `out`, e.g. to encode many images with a single buffer.
+/// Space for [`max_output_len`] bytes is reserved up front
+#[cfg(feature = "alloc")]
+pub fn encode_into_vec<I>(header: QoiHeader, pixels: I, out: &mut alloc::vec::Vec<u8>)
+where
+    I: IntoIterator<Item = Pixel>,
+{
+    out.clear();
+    if let Some(len) = max_output_len(&header) {
+        out.reserve(len);
+    }
+    out.extend(QoiEncoder::new(header, pixels.into_iter()));
+}
+
+/// Encode pixels given as 4 bytes each in the channel order b, g, r, a, see [`QoiEncoder::new`]
+///
+/// This produces the same bytes as encoding the same pixels given in rgba order,
+/// without swizzling the whole buffer up front. A trailing part of `bgra` shorter than 4 bytes is ignored
+#[cfg(feature = "alloc")]
+pub fn encode_bgra_bytes(header: QoiHeader, bgra: &[u8]) -> alloc::vec::Vec<u8> {
+    encode_to_vec(
+        header,
+        bgra.chunks_exact(4)
+            .map(|bgra| Pixel::rgba(bgra[2], bgra[1], bgra[0], bgra[3])),
+    )
+}
+
+/// Encode a contiguous slice of pixels, producing the same bytes as [`QoiEncoder::new`]
+///
+/// Runs are found by scanning ahead in the slice instead of comparing one pixel at a time,
+/// which is considerably faster for images with large areas of a single color
+#[cfg(feature = "alloc")]
+pub fn encode_slice(header: QoiHeader, pixels: &[Pixel]) -> alloc::vec::Vec<u8> {
+    let mut out = alloc::vec::Vec::new();
+    if let Some(len) = max_output_len(&header) {
+        out.reserve(len);
+    }
+    out.extend(header.to_bytes());
+
+    let mut state = CoderState::default();
+    let mut rest = pixels;
+    while let Some((pixel, tail)) = rest.split_first() {
+        if *pixel == state.previous {
+            let run = rest
+                .iter()
+                .position(|next| *next != state.previous)
+                .unwrap_or(rest.len());
+
+            // the index is 0 initialized, so the index needs to be updated in case the first pixel is (0, 0, 0, 255)
+            state.update_index(*pixel);
+
+            let max_run = QoiChunk::MAX_RUN as usize;
+            for _ in 0..run / max_run {
+                out.extend(QoiChunk::new_run(max_run as u8));
+            }
+            if run % max_run > 0 {
+                out.extend(QoiChunk::new_run((run % max_run) as u8));
+            }
+
+            rest = &rest[run..];
+        } else {
+            out.extend(encode_pixel(
+                &mut state,
+                *pixel,
+                ChunkPreference::IndexFirst,
+            ));
+            rest = tail;
+        }
+    }
+
+    out.extend(QOI_FOOTER);
+    out
 }
 
 /// An encoder used to turn a Qoi Format File Header and Pixels into bytes
 pub struct QoiEncoder<I: Iterator<Item = Pixel>> {
     header_bytes: core::array::IntoIter<u8, 14>,
     chunks: core::iter::Flatten<QoiChunkEncoder<I>>,
-    footer_bytes: core::array::IntoIter<u8, 8>,
+    footer_bytes: core::iter::Take<core::array::IntoIter<u8, 8>>,
 }
 
 impl<I> QoiEncoder<I>
@@ -157,12 +448,129 @@ where
     /// ensure that the iterator results in the right amount of pixel or the resulting image will be malformed!
     #[doc(alias = "save")]
     pub fn new(header: QoiHeader, pixels: I) -> Self {
+        Self::with_options(header, pixels, EncoderOptions::default())
+    }
+
+    /// Create a new streaming Qoi Encoder using the provided options, see [`QoiEncoder::new`]
+    pub fn with_options(header: QoiHeader, pixels: I, options: EncoderOptions) -> Self {
+        let footer_len = if options.footer { QOI_FOOTER.len() } else { 0 };
+        let chunks = QoiChunkEncoder::with_options(pixels, options).channels(header.channels);
+
         Self {
-            chunks: QoiChunkEncoder::new(pixels).flatten(),
+            chunks: chunks_to_bytes(chunks),
             header_bytes: header.to_bytes().into_iter(),
-            footer_bytes: QOI_FOOTER.into_iter(),
+            footer_bytes: QOI_FOOTER.into_iter().take(footer_len),
         }
     }
+
+    /// Create a new streaming Qoi Encoder that omits the footer
+    ///
+    /// This is meant for embedding the qoi data into a container that already delimits it,
+    /// the result is **not** a standalone qoi file!
+    /// A decoder needs to rely on the pixel count from the header to find the end of the data,
+    /// e.g. [`QoiDecoder::new_bounded`](crate::decode::QoiDecoder::new_bounded)
+    pub fn new_no_footer(header: QoiHeader, pixels: I) -> Self {
+        Self::with_options(header, pixels, EncoderOptions::default().footer(false))
+    }
+}
+
+impl<'a> QoiEncoder<core::iter::Copied<core::slice::Iter<'a, Pixel>>> {
+    /// Create a new streaming Qoi Encoder for a slice of pixels,
+    /// picking the channels via [`QoiChannels::from_pixels`] and building the header from it
+    ///
+    /// Returns the header alongside the encoder, so the caller knows which channels were chosen
+    pub fn new_autodetect(
+        width: u32,
+        height: u32,
+        color_space: QoiColorSpace,
+        pixels: &'a [Pixel],
+    ) -> (QoiHeader, Self) {
+        let channels = QoiChannels::from_pixels(pixels);
+        let header = QoiHeader::new(width, height, channels, color_space);
+        (header, Self::new(header, pixels.iter().copied()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I> QoiEncoder<I>
+where
+    I: Iterator<Item = Pixel>,
+{
+    /// Write the encoded bytes to `writer` as they are produced, returning the number of bytes written
+    ///
+    /// Unlike collecting the bytes first, a failing writer is noticed as soon as a write fails.
+    /// The error includes the number of bytes the writer accepted before failing.
+    /// The writer is flushed at the end, consider wrapping it in a [`BufWriter`](std::io::BufWriter)
+    pub fn write_to<W: std::io::Write>(mut self, writer: &mut W) -> Result<usize, WriteError> {
+        let mut buf = [0; 4096];
+        let mut written = 0;
+
+        loop {
+            let mut len = 0;
+            for (slot, byte) in buf.iter_mut().zip(self.by_ref()) {
+                *slot = byte;
+                len += 1;
+            }
+            if len == 0 {
+                break;
+            }
+
+            let mut pending = &buf[..len];
+            while !pending.is_empty() {
+                match writer.write(pending) {
+                    Ok(0) => {
+                        let error = std::io::ErrorKind::WriteZero.into();
+                        return Err(WriteError { written, error });
+                    }
+                    Ok(n) => {
+                        written += n;
+                        pending = &pending[n..];
+                    }
+                    Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(error) => return Err(WriteError { written, error }),
+                }
+            }
+        }
+
+        writer
+            .flush()
+            .map_err(|error| WriteError { written, error })?;
+        Ok(written)
+    }
+}
+
+/// A failed write of [`QoiEncoder::write_to`]
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct WriteError {
+    /// The number of bytes the writer accepted before the error
+    pub written: usize,
+    pub error: std::io::Error,
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "write failed after {} bytes: {}",
+            self.written, self.error
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<WriteError> for std::io::Error {
+    fn from(error: WriteError) -> Self {
+        error.error
+    }
 }
 
 impl<I> FusedIterator for QoiEncoder<I>
@@ -183,3 +591,152 @@ impl<I: Iterator<Item = Pixel>> Iterator for QoiEncoder<I> {
             .or_else(|| self.footer_bytes.next())
     }
 }
+
+/// A streaming Qoi Encoder for a fallible pixel source
+///
+/// Encoding stops at the first error, which is yielded in place of the remaining bytes.
+/// Like [`QoiEncoder`] this does not stop after width * height pixels on its own
+pub struct TryQoiEncoder<I, E> {
+    header_bytes: core::array::IntoIter<u8, 14>,
+    chunks: QoiChunkEncoder<ErrorShunt<I, E>>,
+    chunk_bytes: ChunkBuf,
+    footer_bytes: core::iter::Take<core::array::IntoIter<u8, 8>>,
+    failed: bool,
+}
+
+impl<I, E> TryQoiEncoder<I, E>
+where
+    I: Iterator<Item = Result<Pixel, E>>,
+{
+    /// Create a new streaming Qoi Encoder for a fallible pixel source, see [`QoiEncoder::new`]
+    pub fn new(header: QoiHeader, pixels: I) -> Self {
+        Self::with_options(header, pixels, EncoderOptions::default())
+    }
+
+    /// Create a new streaming Qoi Encoder for a fallible pixel source using the provided options,
+    /// see [`QoiEncoder::with_options`]
+    pub fn with_options(header: QoiHeader, pixels: I, options: EncoderOptions) -> Self {
+        let footer_len = if options.footer { QOI_FOOTER.len() } else { 0 };
+        let chunks = QoiChunkEncoder::with_options(ErrorShunt::new(pixels), options)
+            .channels(header.channels);
+
+        Self {
+            header_bytes: header.to_bytes().into_iter(),
+            chunks,
+            chunk_bytes: ChunkBuf::new(),
+            footer_bytes: QOI_FOOTER.into_iter().take(footer_len),
+            failed: false,
+        }
+    }
+}
+
+impl<I, E> Iterator for TryQoiEncoder<I, E>
+where
+    I: Iterator<Item = Result<Pixel, E>>,
+{
+    type Item = Result<u8, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        if let Some(byte) = self.header_bytes.next() {
+            return Some(Ok(byte));
+        }
+
+        loop {
+            if let Some(byte) = self.chunk_bytes.next() {
+                return Some(Ok(byte));
+            }
+
+            let chunk = self.chunks.next();
+
+            // the chunk flushed by the error is dropped, encoding is aborted anyway
+            if let Some(error) = self.chunks.pixel.take_error() {
+                self.failed = true;
+                return Some(Err(error));
+            }
+
+            match chunk {
+                Some(chunk) => self.chunk_bytes = chunk.into_iter(),
+                None => return self.footer_bytes.next().map(Ok),
+            }
+        }
+    }
+}
+
+/// An encoder to which pixels are pushed one at a time, instead of pulling them from an iterator
+///
+/// The bytes returned by [`PushEncoder::push`] and [`PushEncoder::finish`] in order make up the qoi file
+pub struct PushEncoder {
+    header_bytes: Option<[u8; 14]>,
+    state: CoderState,
+}
+
+impl PushEncoder {
+    pub fn new(header: QoiHeader) -> Self {
+        Self {
+            header_bytes: Some(header.to_bytes()),
+            state: CoderState::default(),
+        }
+    }
+
+    /// Push the next pixel, returning the bytes that are ready to be written
+    ///
+    /// For a pixel continuing a run this is usually empty,
+    /// as the run chunk is only complete once the run ends
+    pub fn push(&mut self, pixel: Pixel) -> impl Iterator<Item = u8> {
+        let header_bytes = self.header_bytes.take();
+
+        let (run, chunk) = if pixel == self.state.previous {
+            self.state.run += 1;
+            if self.state.run == 1 {
+                // see QoiChunkEncoder, the index might not contain the previous pixel yet
+                self.state.update_index(pixel);
+            }
+            if self.state.run == QoiChunk::MAX_RUN {
+                self.state.run = 0;
+                (Some(QoiChunk::new_run(QoiChunk::MAX_RUN)), None)
+            } else {
+                (None, None)
+            }
+        } else {
+            (
+                self.take_run(),
+                Some(encode_pixel(
+                    &mut self.state,
+                    pixel,
+                    ChunkPreference::IndexFirst,
+                )),
+            )
+        };
+
+        header_bytes
+            .into_iter()
+            .flatten()
+            .chain(run.into_iter().chain(chunk).flatten())
+    }
+
+    /// Finish the image, returning the remaining bytes including the footer
+    ///
+    /// # Note
+    /// just like for [`QoiEncoder`] it is up to the caller to push exactly width * height pixel
+    pub fn finish(mut self) -> impl Iterator<Item = u8> {
+        let run = self.take_run();
+
+        self.header_bytes
+            .into_iter()
+            .flatten()
+            .chain(run.into_iter().flatten())
+            .chain(QOI_FOOTER)
+    }
+
+    fn take_run(&mut self) -> Option<QoiChunk> {
+        (self.state.run > 0).then(|| {
+            let run = QoiChunk::new_run(self.state.run);
+            self.state.run = 0;
+            run
+        })
+    }
+}
@@ -0,0 +1,74 @@
+//! Conversions from and to the image buffers of the [`image`](::image) crate
+
+use alloc::vec::Vec;
+
+use ::image::{RgbImage, RgbaImage};
+
+use crate::{
+    decode::{DecodeError, DecoderOptions, QoiDecoder},
+    encode::QoiEncoder,
+    types::{Pixel, QoiChannels, QoiColorSpace, QoiHeader},
+};
+
+/// Encode an rgba image as qoi, streaming the pixels straight from its buffer
+pub fn encode_rgba_image(image: &RgbaImage) -> Vec<u8> {
+    let header = QoiHeader::new(
+        image.width(),
+        image.height(),
+        QoiChannels::Rgba,
+        QoiColorSpace::SRgbWithLinearAlpha,
+    );
+    let pixels = image
+        .as_raw()
+        .chunks_exact(4)
+        .map(|px| Pixel::rgba(px[0], px[1], px[2], px[3]));
+    QoiEncoder::new(header, pixels).collect()
+}
+
+/// Encode an rgb image as qoi, streaming the pixels straight from its buffer
+pub fn encode_rgb_image(image: &RgbImage) -> Vec<u8> {
+    let header = QoiHeader::new(
+        image.width(),
+        image.height(),
+        QoiChannels::Rgb,
+        QoiColorSpace::SRgbWithLinearAlpha,
+    );
+    let pixels = image
+        .as_raw()
+        .chunks_exact(3)
+        .map(|px| Pixel::rgb(px[0], px[1], px[2]));
+    QoiEncoder::new(header, pixels).collect()
+}
+
+/// Decode a qoi into an rgba image, regardless of the channels declared by the header
+///
+/// Fails with [`DecodeError::PixelCountMismatch`] if the data does not contain width * height pixels
+pub fn decode_rgba_image(bytes: &[u8]) -> Result<RgbaImage, DecodeError> {
+    decode_image(bytes, |px, buf| buf.extend([px.r, px.g, px.b, px.a]))
+}
+
+/// Decode a qoi into an rgb image, dropping the alpha channel
+///
+/// Fails with [`DecodeError::PixelCountMismatch`] if the data does not contain width * height pixels
+pub fn decode_rgb_image(bytes: &[u8]) -> Result<RgbImage, DecodeError> {
+    decode_image(bytes, |px, buf| buf.extend([px.r, px.g, px.b]))
+}
+
+fn decode_image<P: ::image::Pixel<Subpixel = u8>>(
+    bytes: &[u8],
+    mut push: impl FnMut(Pixel, &mut Vec<u8>),
+) -> Result<::image::ImageBuffer<P, Vec<u8>>, DecodeError> {
+    let options = DecoderOptions::new().enforce_pixel_count(true);
+    let (header, mut decoder) = QoiDecoder::with_options(bytes.iter().copied(), options)?;
+
+    // the buffer is not pre-allocated based on the header, see decode_fuzz_safe
+    let mut buf = Vec::new();
+    decoder.by_ref().for_each(|px| push(px, &mut buf));
+    decoder.finish()?;
+
+    // finish checked that the buffer contains exactly width * height pixels
+    Ok(
+        ::image::ImageBuffer::from_raw(header.width, header.height, buf)
+            .expect("buffer should match the image size"),
+    )
+}
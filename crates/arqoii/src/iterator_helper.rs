@@ -1,8 +1,16 @@
-use core::{iter::FusedIterator, mem::MaybeUninit};
+use core::iter::FusedIterator;
 
+/// An iterator adapter allowing to peek at the next N items
+///
+/// The peek buffer is only touched once [`PeekN::peek`] has been called,
+/// as long as it is empty [`Iterator::next`] directly forwards to the inner iterator
 pub(crate) struct PeekN<const N: usize, I, Item> {
     iter: I,
     peek: [Option<Item>; N],
+    /// the index of the first buffered item in peek
+    head: usize,
+    /// the number of buffered items in peek
+    len: usize,
 }
 
 impl<const N: usize, I, Item> PeekN<N, I, Item> {
@@ -10,6 +18,8 @@ impl<const N: usize, I, Item> PeekN<N, I, Item> {
         Self {
             iter,
             peek: [(); N].map(|_| None),
+            head: 0,
+            len: 0,
         }
     }
 
@@ -18,39 +28,20 @@ impl<const N: usize, I, Item> PeekN<N, I, Item> {
     where
         I: Iterator<Item = Item>,
     {
-        // rotate the first remaining peek value to the front
-        let rotate = self
-            .peek
-            .iter()
-            .enumerate()
-            .find_map(|(idx, elem)| elem.is_some().then_some(idx))
-            .unwrap_or(0);
-        self.peek.rotate_left(rotate);
+        // rotate the buffered items to the front
+        self.peek.rotate_left(self.head);
+        self.head = 0;
 
-        let mut peek = [(); N].map(|_| MaybeUninit::uninit());
-        let mut count = 0;
-
-        for elem in self.peek.iter_mut().flat_map(|elem| {
-            if elem.is_none() {
-                *elem = self.iter.next();
-            }
-            elem.as_ref()
-        }) {
-            peek[count].write(elem);
-            count += 1;
+        while self.len < N {
+            self.peek[self.len] = Some(self.iter.next()?);
+            self.len += 1;
         }
 
-        if count == N {
-            // Safety count is N and as such all indices 0 to N - 1 have been written to
-            Some(unsafe { core::mem::transmute_copy::<[MaybeUninit<&Item>; N], [&Item; N]>(&peek) })
-
-            // once https://github.com/rust-lang/rust/issues/96097 is stabilized use
-            // Some(unsafe { peek.transpose().assume_init()})
-            // or
-            // Some(unsafe { MaybeUninit::array_assume_init(peek) })
-        } else {
-            None
-        }
+        // all N slots are filled at this point
+        let peek = &self.peek;
+        Some(core::array::from_fn(|idx| {
+            peek[idx].as_ref().expect("all peek slots should be filled")
+        }))
     }
 }
 
@@ -58,10 +49,14 @@ impl<const N: usize, I: Iterator> Iterator for PeekN<N, I, I::Item> {
     type Item = I::Item;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.peek
-            .iter_mut()
-            .find_map(|elem| elem.take())
-            .or_else(|| self.iter.next())
+        if self.len == 0 {
+            return self.iter.next();
+        }
+
+        let item = self.peek[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        item
     }
 }
 
@@ -71,3 +66,39 @@ where
     PeekN<N, I, Item>: Iterator,
 {
 }
+
+/// An iterator adapter yielding the `Ok` values of the inner iterator and stopping at the first `Err`
+///
+/// The error is kept until it is taken with [`ErrorShunt::take_error`]
+pub(crate) struct ErrorShunt<I, E> {
+    iter: I,
+    error: Option<E>,
+}
+
+impl<I, E> ErrorShunt<I, E> {
+    pub fn new(iter: I) -> Self {
+        Self { iter, error: None }
+    }
+
+    pub fn take_error(&mut self) -> Option<E> {
+        self.error.take()
+    }
+}
+
+impl<T, E, I: Iterator<Item = Result<T, E>>> Iterator for ErrorShunt<I, E> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        match self.iter.next()? {
+            Ok(item) => Some(item),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
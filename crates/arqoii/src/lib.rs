@@ -1,8 +1,30 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 pub use arqoii_types as types;
-pub use arqoii_types::{QOI_FOOTER, QOI_MAGIC};
+pub use arqoii_types::{ends_with_valid_footer, starts_with_magic, QOI_FOOTER, QOI_MAGIC};
 
+pub mod adapter;
 pub mod decode;
+pub mod digest;
+#[cfg(feature = "alloc")]
+pub mod downsample;
 pub mod encode;
+#[cfg(feature = "image")]
+pub mod image;
 mod iterator_helper;
+#[cfg(feature = "alpha-mask")]
+pub mod mask;
+pub mod palette;
+pub mod prelude;
+#[cfg(feature = "alloc")]
+pub mod split;
+pub mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tile;
+pub mod transcode;
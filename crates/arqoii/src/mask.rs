@@ -0,0 +1,68 @@
+//! Storing single channel alpha masks as qoi
+//!
+//! Qoi has no single channel mode, so masks are packed into an opaque rgb image:
+//! every mask value `v` becomes the pixel `(v, v, v, 255)`
+//! and the header declares [`QoiChannels::Rgb`] and [`QoiColorSpace::AllChannelsLinear`].
+//! Equal color channels keep consecutive mask values cheap to encode as Diff or Luma Chunks.
+//!
+//! When decoding only the red channel is read back.
+//!
+//! [`split_alpha`] and [`merge_alpha`] support storing an image as two planes,
+//! the opaque color encoded as a regular rgb qoi image and the alpha as a mask.
+
+use alloc::vec::Vec;
+
+use crate::{
+    decode::{decode_fuzz_safe, DecodeError},
+    encode::QoiEncoder,
+    types::{Pixel, QoiChannels, QoiColorSpace, QoiHeader},
+};
+
+/// Encode a width * height alpha mask as qoi, see the [module documentation](self) for the packing
+///
+/// # Note
+/// just like for [`QoiEncoder`] it is up to the caller to provide exactly width * height mask values
+pub fn encode_alpha_mask(
+    width: u32,
+    height: u32,
+    mask: &[u8],
+) -> QoiEncoder<impl Iterator<Item = Pixel> + '_> {
+    debug_assert_eq!(mask.len() as u64, width as u64 * height as u64);
+
+    let header = QoiHeader::new(
+        width,
+        height,
+        QoiChannels::Rgb,
+        QoiColorSpace::AllChannelsLinear,
+    );
+    QoiEncoder::new(header, mask.iter().map(|&v| Pixel::rgb(v, v, v)))
+}
+
+/// Decode an alpha mask encoded by [`encode_alpha_mask`], returning the header and the mask values
+///
+/// Decoding is bounded by the header just like [`decode_fuzz_safe`]
+pub fn decode_alpha_mask(bytes: &[u8]) -> Result<(QoiHeader, Vec<u8>), DecodeError> {
+    let (header, pixels) = decode_fuzz_safe(bytes)?;
+    Ok((header, pixels.into_iter().map(|pixel| pixel.r).collect()))
+}
+
+/// Split pixels into their opaque colors and their alpha values
+///
+/// The colors can be encoded as a [`QoiChannels::Rgb`] image and the alpha values via [`encode_alpha_mask`]
+pub fn split_alpha(pixels: &[Pixel]) -> (Vec<Pixel>, Vec<u8>) {
+    pixels
+        .iter()
+        .map(|pixel| (Pixel { a: 255, ..*pixel }, pixel.a))
+        .unzip()
+}
+
+/// Combine colors and alpha values split by [`split_alpha`] into pixels again
+///
+/// The color alpha is replaced, stops at the end of the shorter input
+pub fn merge_alpha(colors: &[Pixel], alpha: &[u8]) -> Vec<Pixel> {
+    colors
+        .iter()
+        .zip(alpha)
+        .map(|(pixel, &a)| Pixel { a, ..*pixel })
+        .collect()
+}
@@ -0,0 +1,26 @@
+use crate::types::Pixel;
+
+/// Find the palette entry closest to `pixel` by squared euclidean distance over the RGBA channels
+///
+/// Mapping pixels to a reduced palette before encoding can increase the number of runs and index hits.
+/// Returns the index of the closest entry, the first one in case of a tie.
+///
+/// `None` means the palette is empty, as there is no closest entry then.
+/// This is reported instead of panicking, as the palette often comes from user input
+pub fn nearest_in_palette(pixel: &Pixel, palette: &[Pixel]) -> Option<usize> {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| squared_distance(pixel, entry))
+        .map(|(idx, _)| idx)
+}
+
+fn squared_distance(a: &Pixel, b: &Pixel) -> u32 {
+    [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)]
+        .into_iter()
+        .map(|(a, b)| {
+            let d = a.abs_diff(b) as u32;
+            d * d
+        })
+        .sum()
+}
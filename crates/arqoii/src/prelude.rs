@@ -0,0 +1,5 @@
+//! The commonly used items, for glob importing with `use arqoii::prelude::*;`
+
+pub use crate::decode::{QoiChunkDecoder, QoiDecoder};
+pub use crate::encode::{QoiChunkEncoder, QoiEncoder};
+pub use crate::types::{Pixel, QoiChannels, QoiColorSpace, QoiHeader};
@@ -0,0 +1,50 @@
+use alloc::vec::Vec;
+
+use crate::iterator_helper::PeekN;
+use crate::{QOI_FOOTER, QOI_MAGIC};
+
+/// Split a concatenation of qoi files into the individual files
+///
+/// A file ends where the footer is directly followed by the magic of the next file,
+/// or at the end of the stream.
+///
+/// # Note
+/// this only looks at the markers and does not validate the files.
+/// Chunk data that happens to contain the footer followed by the magic splits a file in two
+pub fn split_qoi_stream<B>(bytes: B) -> SplitQoiStream<B::IntoIter>
+where
+    B: IntoIterator<Item = u8>,
+{
+    SplitQoiStream {
+        bytes: PeekN::new(bytes.into_iter()),
+    }
+}
+
+/// An iterator over the individual qoi files of a concatenation
+///
+/// Created by [`split_qoi_stream`]
+pub struct SplitQoiStream<I> {
+    bytes: PeekN<4, I, u8>,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for SplitQoiStream<I> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut file = Vec::new();
+
+        while let Some(byte) = self.bytes.next() {
+            file.push(byte);
+
+            if file.ends_with(&QOI_FOOTER) {
+                if let Some(peek) = self.bytes.peek() {
+                    if peek.map(|byte| *byte) == QOI_MAGIC {
+                        return Some(file);
+                    }
+                }
+            }
+        }
+
+        (!file.is_empty()).then_some(file)
+    }
+}
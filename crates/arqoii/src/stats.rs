@@ -0,0 +1,91 @@
+use core::iter::FusedIterator;
+
+use crate::types::QoiChunk;
+
+/// The number of chunks of each kind and the bytes they take up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChunkStats {
+    pub rgb: u64,
+    pub rgba: u64,
+    pub index: u64,
+    pub diff: u64,
+    pub luma: u64,
+    pub run: u64,
+    /// the number of pixels covered by all chunks
+    pub pixels: u64,
+    /// the number of bytes of all chunks, excluding header and footer
+    pub bytes: u64,
+}
+
+impl ChunkStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the chunk
+    pub fn record(&mut self, chunk: &QoiChunk) {
+        let counter = match chunk {
+            QoiChunk::Rgb { .. } => &mut self.rgb,
+            QoiChunk::Rgba { .. } => &mut self.rgba,
+            QoiChunk::Index { .. } => &mut self.index,
+            QoiChunk::Diff { .. } => &mut self.diff,
+            QoiChunk::Luma { .. } => &mut self.luma,
+            QoiChunk::Run { .. } => &mut self.run,
+        };
+        *counter += 1;
+        self.pixels += chunk.pixel_count() as u64;
+        self.bytes += chunk.encoded_len() as u64;
+    }
+
+    /// The total number of chunks
+    pub fn chunks(&self) -> u64 {
+        self.rgb + self.rgba + self.index + self.diff + self.luma + self.run
+    }
+}
+
+/// An iterator passing chunks through unchanged, while counting them
+///
+/// Iterate it by reference and read [`WithStats::stats`] afterwards,
+/// to get both the chunks and their statistics in a single pass.
+///
+/// Created by [`QoiChunkEncoder::with_stats`](crate::encode::QoiChunkEncoder::with_stats)
+/// or [`QoiChunkDecoder::with_stats`](crate::decode::QoiChunkDecoder::with_stats)
+pub struct WithStats<I> {
+    chunks: I,
+    stats: ChunkStats,
+}
+
+impl<I> WithStats<I> {
+    pub(crate) fn new(chunks: I) -> Self {
+        Self {
+            chunks,
+            stats: ChunkStats::new(),
+        }
+    }
+
+    /// The statistics of the chunks produced so far
+    pub fn stats(&self) -> &ChunkStats {
+        &self.stats
+    }
+}
+
+impl<I: Iterator<Item = QoiChunk>> Iterator for WithStats<I> {
+    type Item = QoiChunk;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunks.next()?;
+        self.stats.record(&chunk);
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<I> FusedIterator for WithStats<I>
+where
+    WithStats<I>: Iterator,
+    I: FusedIterator,
+{
+}
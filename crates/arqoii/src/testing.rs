@@ -0,0 +1,27 @@
+use alloc::vec::Vec;
+
+use crate::types::Pixel;
+
+/// Generate `width` * `height` pixels of random noise, the same seed always produces the same pixels
+///
+/// Noise is the worst case for qoi, nearly every pixel ends up as an Rgb Chunk,
+/// or with `with_alpha` as an Rgba Chunk as the alpha channel is random as well.
+/// Without `with_alpha` all pixels are opaque
+pub fn gen_noise(width: u32, height: u32, seed: u64, with_alpha: bool) -> Vec<Pixel> {
+    let mut state = seed;
+    let mut next = move || {
+        // splitmix64, which unlike xorshift is fine with a seed of 0
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    (0..width as u64 * height as u64)
+        .map(|_| {
+            let [r, g, b, a, ..] = next().to_le_bytes();
+            Pixel::rgba(r, g, b, if with_alpha { a } else { 255 })
+        })
+        .collect()
+}
@@ -0,0 +1,231 @@
+use core::iter::FusedIterator;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::types::{Pixel, QoiHeader};
+
+/// Split an image into tiles of `tile_width` * `tile_height` pixels, e.g. to encode each tile as its own qoi
+///
+/// Tiles are produced row by row, each one as `(tile_x, tile_y, header, pixels)`
+/// where `tile_x` and `tile_y` count tiles, not pixels.
+/// The tiles at the right and bottom edge are smaller if the image size is not a multiple of the tile size.
+/// The tile headers keep the channels and color space of `header`.
+///
+/// This needs the whole image as a slice, so that tiles can borrow their pixels without copying them.
+/// To split a pixel stream, e.g. straight from a [`QoiDecoder`](crate::decode::QoiDecoder), use [`tiles_from_stream`]
+///
+/// # Panics
+/// if `tile_width` or `tile_height` is 0 or `pixels` contains fewer than width * height pixels
+pub fn tiles(pixels: &[Pixel], header: QoiHeader, tile_width: u32, tile_height: u32) -> Tiles<'_> {
+    assert!(
+        tile_width > 0 && tile_height > 0,
+        "tiles need to be at least 1x1"
+    );
+    assert!(
+        pixels.len() as u64 >= header.pixel_count(),
+        "fewer pixels than declared by the header"
+    );
+
+    Tiles {
+        pixels,
+        header,
+        tile_width,
+        tile_height,
+        x: 0,
+        y: 0,
+    }
+}
+
+/// An iterator over the tiles of an image
+///
+/// Created by [`tiles`]
+pub struct Tiles<'a> {
+    pixels: &'a [Pixel],
+    header: QoiHeader,
+    tile_width: u32,
+    tile_height: u32,
+    /// the pixel position of the next tile
+    x: u32,
+    y: u32,
+}
+
+impl<'a> Iterator for Tiles<'a> {
+    type Item = (u32, u32, QoiHeader, TilePixels<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.header.width == 0 || self.y >= self.header.height {
+            return None;
+        }
+
+        let width = self.tile_width.min(self.header.width - self.x);
+        let height = self.tile_height.min(self.header.height - self.y);
+
+        // computed as u64 as y * width can exceed u32::MAX,
+        // the result fits into usize as it is less than pixel count which is at most pixels.len()
+        let start = (self.y as u64 * self.header.width as u64 + self.x as u64) as usize;
+        let tile = (
+            self.x / self.tile_width,
+            self.y / self.tile_height,
+            QoiHeader::new(width, height, self.header.channels, self.header.color_space),
+            TilePixels {
+                pixels: &self.pixels[start..],
+                stride: self.header.width as usize,
+                width: width as usize,
+                rows: height as usize,
+                row: [].iter(),
+            },
+        );
+
+        self.x += width;
+        if self.x >= self.header.width {
+            self.x = 0;
+            self.y += height;
+        }
+
+        Some(tile)
+    }
+}
+
+impl FusedIterator for Tiles<'_> {}
+
+/// An iterator over the pixels of a single tile, row by row
+pub struct TilePixels<'a> {
+    /// the pixels starting at the first pixel of the next row
+    pixels: &'a [Pixel],
+    stride: usize,
+    width: usize,
+    rows: usize,
+    row: core::slice::Iter<'a, Pixel>,
+}
+
+impl Iterator for TilePixels<'_> {
+    type Item = Pixel;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(pixel) = self.row.next() {
+                return Some(*pixel);
+            }
+            if self.rows == 0 {
+                return None;
+            }
+
+            self.rows -= 1;
+            self.row = self.pixels[..self.width].iter();
+            if self.rows > 0 {
+                self.pixels = &self.pixels[self.stride..];
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.row.len() + self.rows * self.width;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for TilePixels<'_> {}
+
+impl FusedIterator for TilePixels<'_> {}
+
+/// Like [`tiles`], but splitting a stream of pixels instead of a slice holding the whole image
+///
+/// Only one row of tiles, i.e. `tile_height` rows of the image, is buffered at a time,
+/// so each tile is produced as its own `Vec`.
+/// Stops early if the pixels run out before a row of tiles is complete.
+///
+/// # Panics
+/// if `tile_width` or `tile_height` is 0
+#[cfg(feature = "alloc")]
+pub fn tiles_from_stream<P>(
+    pixels: P,
+    header: QoiHeader,
+    tile_width: u32,
+    tile_height: u32,
+) -> StreamTiles<P::IntoIter>
+where
+    P: IntoIterator<Item = Pixel>,
+{
+    assert!(
+        tile_width > 0 && tile_height > 0,
+        "tiles need to be at least 1x1"
+    );
+
+    StreamTiles {
+        pixels: pixels.into_iter(),
+        header,
+        tile_width,
+        tile_height,
+        band: Vec::new(),
+        x: 0,
+        y: 0,
+    }
+}
+
+/// An iterator over the tiles of a pixel stream
+///
+/// Created by [`tiles_from_stream`]
+#[cfg(feature = "alloc")]
+pub struct StreamTiles<I> {
+    pixels: I,
+    header: QoiHeader,
+    tile_width: u32,
+    tile_height: u32,
+    /// the rows of the image covered by the current row of tiles
+    band: Vec<Pixel>,
+    /// the pixel position of the next tile
+    x: u32,
+    y: u32,
+}
+
+#[cfg(feature = "alloc")]
+impl<I: Iterator<Item = Pixel>> Iterator for StreamTiles<I> {
+    type Item = (u32, u32, QoiHeader, Vec<Pixel>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.header.width == 0 || self.y >= self.header.height {
+            return None;
+        }
+
+        let width = self.tile_width.min(self.header.width - self.x);
+        let height = self.tile_height.min(self.header.height - self.y);
+        let stride = self.header.width as usize;
+
+        if self.x == 0 {
+            let len = (height as usize).saturating_mul(stride);
+            self.band.clear();
+            self.band.extend(self.pixels.by_ref().take(len));
+            if self.band.len() < len {
+                // the pixels ran out, don't produce incomplete tiles
+                self.y = self.header.height;
+                return None;
+            }
+        }
+
+        let x = self.x as usize;
+        let tile = self
+            .band
+            .chunks_exact(stride)
+            .flat_map(|row| &row[x..x + width as usize])
+            .copied()
+            .collect();
+        let tile = (
+            self.x / self.tile_width,
+            self.y / self.tile_height,
+            QoiHeader::new(width, height, self.header.channels, self.header.color_space),
+            tile,
+        );
+
+        self.x += width;
+        if self.x >= self.header.width {
+            self.x = 0;
+            self.y += height;
+        }
+
+        Some(tile)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<I: Iterator<Item = Pixel>> FusedIterator for StreamTiles<I> {}
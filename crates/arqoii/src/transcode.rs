@@ -0,0 +1,73 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::{
+    decode::{DecodeError, DecoderOptions, QoiDecoder},
+    encode::QoiEncoder,
+    types::{Pixel, QoiChannels, QoiHeader},
+};
+
+/// Decode a qoi file and encode it again, keeping the header
+///
+/// This normalizes files produced by other encoders to the chunk choices of [`QoiEncoder`],
+/// the pixels stay the same.
+/// All width * height pixels declared by the header are decoded before encoding,
+/// so truncated or otherwise broken input is reported as an error instead of being re-encoded
+#[cfg(feature = "alloc")]
+pub fn recanonicalize(qoi: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let (header, pixels) = decode_complete(qoi)?;
+    Ok(QoiEncoder::new(header, pixels.into_iter()).collect())
+}
+
+/// A summary of what a transcode changed about the image
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TranscodeSummary {
+    /// whether any pixel lost (partial) transparency, i.e. the conversion was lossy
+    pub alpha_dropped: bool,
+    /// the number of pixels that differ between the input and the output
+    pub pixels_changed: u64,
+}
+
+/// Transcode a qoi file into a qoi file declaring `channels`
+///
+/// When converting to [`QoiChannels::Rgb`] the alpha channel of every pixel is set to 255,
+/// the returned summary reports whether this changed any pixels.
+/// The color space is kept as is.
+#[cfg(feature = "alloc")]
+pub fn transcode(
+    qoi: &[u8],
+    channels: QoiChannels,
+) -> Result<(Vec<u8>, TranscodeSummary), DecodeError> {
+    let (header, mut pixels) = decode_complete(qoi)?;
+
+    let mut summary = TranscodeSummary::default();
+
+    if channels == QoiChannels::Rgb {
+        for pixel in &mut pixels {
+            if pixel.a != 255 {
+                *pixel = Pixel { a: 255, ..*pixel };
+                summary.alpha_dropped = true;
+                summary.pixels_changed += 1;
+            }
+        }
+    }
+
+    let header = QoiHeader::new(header.width, header.height, channels, header.color_space);
+    let bytes = QoiEncoder::new(header, pixels.into_iter()).collect();
+
+    Ok((bytes, summary))
+}
+
+/// Decode all pixels declared by the header, failing if the data is truncated or otherwise broken
+///
+/// Re-encoding the pixels of an incomplete image would produce a file whose header promises more pixels than it holds
+#[cfg(feature = "alloc")]
+fn decode_complete(qoi: &[u8]) -> Result<(QoiHeader, Vec<Pixel>), DecodeError> {
+    let options = DecoderOptions::new().enforce_pixel_count(true);
+    let (header, mut decoder) = QoiDecoder::with_options(qoi.iter().copied(), options)?;
+    let pixels = decoder.by_ref().collect();
+    decoder.finish()?;
+    Ok((header, pixels))
+}
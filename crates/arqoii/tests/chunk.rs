@@ -0,0 +1,119 @@
+use arqoii::decode::bytes_to_chunks;
+use arqoii_types::QoiChunk;
+
+fn all_chunks() -> Vec<QoiChunk> {
+    let mut chunks = vec![];
+
+    let extremes = [0, 1, 127, 128, 254, 255];
+    for r in extremes {
+        for g in extremes {
+            for b in extremes {
+                chunks.push(QoiChunk::new_rgb(r, g, b));
+                for a in extremes {
+                    chunks.push(QoiChunk::new_rgba(r, g, b, a));
+                }
+            }
+        }
+    }
+
+    chunks.extend((0..=63).map(QoiChunk::new_index));
+
+    for dr in -2..=1 {
+        for dg in -2..=1 {
+            for db in -2..=1 {
+                chunks.push(QoiChunk::new_diff(dr, dg, db));
+            }
+        }
+    }
+
+    for dg in -32..=31 {
+        for dr_dg in -8..=7 {
+            for db_dg in -8..=7 {
+                chunks.push(QoiChunk::new_luma(dg, dr_dg, db_dg));
+            }
+        }
+    }
+
+    chunks.extend((1..=62).map(QoiChunk::new_run));
+
+    chunks
+}
+
+#[test]
+fn chunk_round_trip() {
+    for chunk in all_chunks() {
+        let bytes = (&chunk).into_iter().collect::<Vec<_>>();
+        assert_eq!(bytes.len(), chunk.encoded_len(), "{chunk}");
+        assert_eq!(bytes, chunk.clone().into_iter().collect::<Vec<_>>());
+
+        assert_eq!(
+            QoiChunk::decode(&bytes),
+            Some((chunk.clone(), bytes.len())),
+            "{chunk} encoded as {bytes:02x?}"
+        );
+        assert_eq!(
+            QoiChunk::decode(&bytes[..bytes.len() - 1]),
+            None,
+            "{chunk} truncated"
+        );
+    }
+}
+
+#[test]
+fn decode_matches_chunk_decoder() {
+    let chunks = all_chunks();
+    let bytes = chunks.iter().flatten().collect::<Vec<_>>();
+
+    let mut rest = &bytes[..];
+    let mut decoded = vec![];
+    while let Some((chunk, len)) = QoiChunk::decode(rest) {
+        decoded.push(chunk);
+        rest = &rest[len..];
+    }
+
+    assert!(rest.is_empty());
+    assert_eq!(decoded, chunks);
+    assert_eq!(bytes_to_chunks(bytes).collect::<Vec<_>>(), chunks);
+}
+
+#[test]
+fn limits() {
+    assert_eq!(
+        QoiChunk::new_run(QoiChunk::MAX_RUN)
+            .into_iter()
+            .collect::<Vec<_>>(),
+        [0xfd]
+    );
+    assert_eq!(
+        QoiChunk::new_index(QoiChunk::MAX_INDEX)
+            .into_iter()
+            .collect::<Vec<_>>(),
+        [0x3f]
+    );
+
+    // the ranges cover exactly the values representable by the chunks
+    for (dr, dg, db) in [
+        (*QoiChunk::DIFF_RANGE.start(), 0, 0),
+        (0, *QoiChunk::DIFF_RANGE.end(), 0),
+    ] {
+        let chunk = QoiChunk::new_diff(dr, dg, db);
+        let bytes = (&chunk).into_iter().collect::<Vec<_>>();
+        assert_eq!(QoiChunk::decode(&bytes), Some((chunk, 1)));
+    }
+    for (dg, dr_dg, db_dg) in [
+        (
+            *QoiChunk::LUMA_DG_RANGE.start(),
+            *QoiChunk::LUMA_DR_DG_RANGE.end(),
+            0,
+        ),
+        (
+            *QoiChunk::LUMA_DG_RANGE.end(),
+            0,
+            *QoiChunk::LUMA_DR_DG_RANGE.start(),
+        ),
+    ] {
+        let chunk = QoiChunk::new_luma(dg, dr_dg, db_dg);
+        let bytes = (&chunk).into_iter().collect::<Vec<_>>();
+        assert_eq!(QoiChunk::decode(&bytes), Some((chunk, 2)));
+    }
+}
@@ -0,0 +1,270 @@
+use arqoii::adapter::PixelIteratorExt;
+use arqoii::palette;
+use arqoii_types::{CoderState, HeaderError, Pixel, QoiChannels, QoiColorSpace, QoiHeader};
+
+#[test]
+fn apply_diff_wraps() {
+    let px = Pixel::rgba(255, 0, 128, 42);
+
+    assert_eq!(px.apply_diff(1, -2, 0), Pixel::rgba(0, 254, 128, 42));
+}
+
+#[test]
+fn apply_luma_wraps() {
+    let px = Pixel::rgba(250, 10, 0, 42);
+
+    assert_eq!(px.apply_luma(31, -8, 7), Pixel::rgba(17, 41, 38, 42));
+    assert_eq!(px.apply_luma(-32, 7, -8), Pixel::rgba(225, 234, 216, 42));
+}
+
+#[test]
+fn packed_u32() {
+    let px = Pixel::rgba(0x12, 0x34, 0x56, 0x78);
+
+    assert_eq!(px.to_rgba_u32(), 0x12345678);
+    assert_eq!(px.to_argb_u32(), 0x78123456);
+    assert_eq!(Pixel::from_rgba_u32(0x12345678), px);
+    assert_eq!(Pixel::from_argb_u32(0x78123456), px);
+}
+
+#[test]
+fn premultiplied() {
+    assert_eq!(
+        Pixel::rgba(255, 128, 0, 255).to_premultiplied(),
+        Pixel::rgba(255, 128, 0, 255)
+    );
+    assert_eq!(
+        Pixel::rgba(255, 128, 1, 128).to_premultiplied(),
+        Pixel::rgba(128, 64, 1, 128)
+    );
+    assert_eq!(Pixel::rgba(255, 128, 1, 0).to_premultiplied(), Pixel::ZERO);
+}
+
+#[test]
+fn luminance() {
+    assert_eq!(Pixel::rgb(0, 0, 0).luminance(), 0);
+    assert_eq!(Pixel::rgb(255, 255, 255).luminance(), 255);
+    assert_eq!(Pixel::rgb(255, 0, 0).luminance(), 54);
+    assert_eq!(Pixel::rgb(0, 255, 0).luminance(), 182);
+    assert_eq!(Pixel::rgb(0, 0, 255).luminance(), 18);
+    assert_eq!(
+        Pixel::rgba(255, 0, 0, 7).to_grayscale(),
+        Pixel::rgba(54, 54, 54, 7)
+    );
+}
+
+#[test]
+fn eq_rgb() {
+    assert!(Pixel::rgba(1, 2, 3, 4).eq_rgb(&Pixel::rgb(1, 2, 3)));
+    assert!(!Pixel::rgba(1, 2, 3, 4).eq_rgb(&Pixel::rgba(1, 2, 4, 4)));
+}
+
+#[test]
+fn header_display() {
+    assert_eq!(QoiChannels::Rgb.to_string(), "RGB");
+    assert_eq!(QoiChannels::Rgba.to_string(), "RGBA");
+    assert_eq!(
+        QoiColorSpace::SRgbWithLinearAlpha.to_string(),
+        "sRGB (linear alpha)"
+    );
+    assert_eq!(QoiColorSpace::AllChannelsLinear.to_string(), "linear");
+}
+
+#[test]
+fn header_default() {
+    let header = QoiHeader {
+        width: 10,
+        height: 10,
+        ..Default::default()
+    };
+    assert_eq!(
+        header,
+        QoiHeader::new(
+            10,
+            10,
+            QoiChannels::Rgba,
+            QoiColorSpace::SRgbWithLinearAlpha
+        )
+    );
+    assert_eq!(QoiHeader::default().pixel_count(), 0);
+}
+
+#[test]
+fn header_from_bytes() {
+    let header = QoiHeader::new(
+        800,
+        600,
+        QoiChannels::Rgba,
+        QoiColorSpace::AllChannelsLinear,
+    );
+    let bytes = header.to_bytes();
+
+    assert_eq!(QoiHeader::from_bytes(&bytes), Ok(header));
+
+    // only the header is read
+    let mut iter = bytes.into_iter().chain([0xff; 3]);
+    assert_eq!(QoiHeader::read_from(iter.by_ref()), Ok(header));
+    assert_eq!(iter.count(), 3);
+
+    assert_eq!(
+        QoiHeader::read_from(bytes[..13].iter().copied()),
+        Err(HeaderError::UnexpectedEof)
+    );
+
+    let mut file = bytes.to_vec();
+    file.extend([0xff; 3]);
+    assert_eq!(QoiHeader::try_from(file.as_slice()), Ok(header));
+    assert_eq!(
+        QoiHeader::try_from(&bytes[..13]),
+        Err(HeaderError::UnexpectedEof)
+    );
+
+    let mut invalid = bytes;
+    invalid[12] = 5;
+    assert_eq!(
+        QoiHeader::from_bytes(&invalid),
+        Err(HeaderError::InvalidChannels(5))
+    );
+    invalid[0] = b'Q';
+    assert_eq!(
+        QoiHeader::from_bytes(&invalid),
+        Err(HeaderError::InvalidMagic(*b"Qoif"))
+    );
+}
+
+#[test]
+fn clear_coder_state() {
+    let mut state = CoderState {
+        previous: Pixel::rgb(1, 2, 3),
+        run: 12,
+        ..CoderState::default()
+    };
+    state.index[7] = Pixel::rgb(4, 5, 6);
+
+    state.clear();
+
+    let default = CoderState::default();
+    assert_eq!(state.previous, default.previous);
+    assert_eq!(state.index, default.index);
+    assert_eq!(state.run, default.run);
+}
+
+#[test]
+fn file_len_bounds() {
+    let header = |width, height| {
+        QoiHeader::new(
+            width,
+            height,
+            QoiChannels::Rgba,
+            QoiColorSpace::SRgbWithLinearAlpha,
+        )
+    };
+
+    assert_eq!(header(0, 0).min_file_len(), 22);
+    assert_eq!(header(0, 0).max_file_len(), Some(22));
+    assert_eq!(header(62, 1).min_file_len(), 23);
+    assert_eq!(header(63, 1).min_file_len(), 24);
+    assert_eq!(header(10, 10).max_file_len(), Some(22 + 500));
+
+    // (2^32 - 1)^2 * 5 bytes exceeds even a 64 bit usize
+    assert_eq!(header(u32::MAX, u32::MAX).max_file_len(), None);
+
+    for name in ["dice", "qoi_logo", "kodim10"] {
+        let qoi = std::fs::read(format!("tests/test-images/qoi/{name}.qoi")).unwrap();
+        let header = QoiHeader::from_bytes(qoi[..14].try_into().unwrap()).unwrap();
+        assert!((header.min_file_len()..=header.max_file_len().unwrap()).contains(&qoi.len()));
+    }
+}
+
+#[test]
+fn nearest_in_palette() {
+    let palette = [
+        Pixel::rgb(0, 0, 0),
+        Pixel::rgb(255, 255, 255),
+        Pixel::rgba(255, 0, 0, 128),
+        Pixel::rgb(0, 0, 0),
+    ];
+
+    assert_eq!(
+        palette::nearest_in_palette(&Pixel::rgb(10, 20, 30), &palette),
+        Some(0)
+    );
+    assert_eq!(
+        palette::nearest_in_palette(&Pixel::rgb(200, 200, 200), &palette),
+        Some(1)
+    );
+    assert_eq!(
+        palette::nearest_in_palette(&Pixel::rgba(250, 5, 5, 100), &palette),
+        Some(2)
+    );
+}
+
+#[test]
+fn nearest_in_empty_palette() {
+    // there is no closest entry, which is reported instead of panicking
+    assert_eq!(palette::nearest_in_palette(&Pixel::rgb(1, 2, 3), &[]), None);
+    assert_eq!(palette::nearest_in_palette(&Pixel::ZERO, &[]), None);
+}
+
+#[test]
+#[cfg(feature = "bytemuck")]
+fn cast_slice() {
+    let pixels = [Pixel::rgba(1, 2, 3, 4), Pixel::rgba(5, 6, 7, 8)];
+
+    let bytes: &[u8] = bytemuck::cast_slice(&pixels);
+    assert_eq!(bytes, [1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(bytemuck::cast_slice::<u8, Pixel>(bytes), pixels);
+    assert_eq!(<Pixel as bytemuck::Zeroable>::zeroed(), Pixel::ZERO);
+}
+
+#[test]
+fn index_slot() {
+    let pixel = Pixel::rgba(12, 34, 56, 78);
+    assert_eq!(CoderState::index_slot(&pixel), pixel.pixel_hash());
+
+    let mut state = CoderState::default();
+    state.update_index(pixel);
+    assert_eq!(state.index[pixel.pixel_hash() as usize], pixel);
+}
+
+#[test]
+fn color_space_conversion() {
+    let pixels = [Pixel::rgba(0, 128, 255, 128), Pixel::rgba(10, 188, 250, 0)];
+
+    let linear = pixels.iter().copied().to_linear().collect::<Vec<_>>();
+    assert_eq!(
+        linear,
+        [Pixel::rgba(0, 55, 255, 128), Pixel::rgba(1, 128, 244, 0)]
+    );
+
+    let srgb = linear.iter().copied().to_srgb().collect::<Vec<_>>();
+    // dark colors don't survive the round trip
+    assert_eq!(
+        srgb,
+        [Pixel::rgba(0, 128, 255, 128), Pixel::rgba(13, 188, 250, 0)]
+    );
+
+    // the conversions are monotonic and preserve black and white
+    let gray = (0..=255).map(|v| Pixel::rgb(v, v, v));
+    for converted in [
+        gray.clone().to_linear().collect::<Vec<_>>(),
+        gray.to_srgb().collect::<Vec<_>>(),
+    ] {
+        assert_eq!(converted[0], Pixel::rgb(0, 0, 0));
+        assert_eq!(converted[255], Pixel::rgb(255, 255, 255));
+        assert!(converted.windows(2).all(|w| w[0].r <= w[1].r));
+    }
+}
+
+#[test]
+fn magic_and_footer() {
+    let qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let png = std::fs::read("tests/test-images/qoi/dice.png").unwrap();
+
+    assert!(arqoii::starts_with_magic(&qoi));
+    assert!(arqoii::ends_with_valid_footer(&qoi));
+    assert!(!arqoii::starts_with_magic(&png));
+    assert!(!arqoii::ends_with_valid_footer(&png));
+    assert!(!arqoii::ends_with_valid_footer(&qoi[..qoi.len() - 1]));
+    assert!(!arqoii::starts_with_magic(b"qoi"));
+}
@@ -0,0 +1,341 @@
+#[cfg(feature = "alloc")]
+use arqoii::downsample::{downsample_2x_lossless, DownsampleError};
+use arqoii::{decode::QoiDecoder, encode::QoiEncoder, QOI_FOOTER};
+use arqoii_types::{Pixel, QoiChannels, QoiChunk, QoiColorSpace, QoiHeader};
+
+// Synthetic images that are encoded and decoded again with arqoii,
+// there are no reference encodings for these as qoi.h is not vendored.
+
+#[test]
+fn single_color() {
+    round_trip(64, 64, QoiChannels::Rgb, |_, _| Pixel::rgb(12, 34, 56));
+    round_trip(64, 64, QoiChannels::Rgba, |_, _| {
+        Pixel::rgba(12, 34, 56, 78)
+    });
+}
+
+#[test]
+fn gradient() {
+    round_trip(256, 256, QoiChannels::Rgb, |x, y| {
+        Pixel::rgb(x as u8, y as u8, (x + y) as u8)
+    });
+    round_trip(256, 256, QoiChannels::Rgba, |x, y| {
+        Pixel::rgba(x as u8, y as u8, (x ^ y) as u8, (x / 4) as u8)
+    });
+}
+
+#[test]
+fn noise() {
+    let mut seed = 0x9e37_79b9_u32;
+    let mut noise = move || {
+        // xorshift32
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed.to_le_bytes()
+    };
+
+    round_trip(97, 89, QoiChannels::Rgb, |_, _| {
+        let [r, g, b, _] = noise();
+        Pixel::rgb(r, g, b)
+    });
+    round_trip(97, 89, QoiChannels::Rgba, |_, _| {
+        let [r, g, b, a] = noise();
+        Pixel::rgba(r, g, b, a)
+    });
+}
+
+#[test]
+fn random_images() {
+    // pseudo-random images mixing runs, index hits, small and large changes,
+    // the seed is part of every assertion message to reproduce a failure
+    for seed in 1..=64_u32 {
+        let mut state = seed.wrapping_mul(0x9e37_79b9);
+        let mut random = move || {
+            // xorshift32
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        let width = random() % 40;
+        let height = random() % 40;
+        let channels = if random() % 2 == 0 {
+            QoiChannels::Rgb
+        } else {
+            QoiChannels::Rgba
+        };
+
+        let palette = (0..8)
+            .map(|_| Pixel::from_rgba_u32(random()))
+            .collect::<Vec<_>>();
+        let mut previous = Pixel::rgba(0, 0, 0, 255);
+        let pixels = (0..width * height)
+            .map(|_| {
+                let [r, g, b, a] = random().to_le_bytes();
+                previous = match r % 4 {
+                    0 => previous,
+                    1 => palette[g as usize % palette.len()],
+                    2 => {
+                        previous.apply_diff((g % 5) as i8 - 2, (b % 5) as i8 - 2, (a % 5) as i8 - 2)
+                    }
+                    _ => Pixel::rgba(r, g, b, a),
+                };
+                if channels == QoiChannels::Rgb {
+                    previous.a = 255;
+                }
+                previous
+            })
+            .collect::<Vec<_>>();
+
+        let header = QoiHeader::new(width, height, channels, QoiColorSpace::SRgbWithLinearAlpha);
+        let qoi = QoiEncoder::new(header, pixels.iter().copied()).collect::<Vec<_>>();
+
+        let (decoded_header, mut decoder) = QoiDecoder::new_bounded(qoi.into_iter()).unwrap();
+        assert_eq!(decoded_header, header, "seed {seed}");
+        assert_eq!(decoder.by_ref().collect::<Vec<_>>(), pixels, "seed {seed}");
+        assert_eq!(decoder.finish(), Ok(()), "seed {seed}");
+    }
+}
+
+#[test]
+#[cfg(feature = "testing")]
+fn generated_noise() {
+    use arqoii::testing::gen_noise;
+
+    assert_eq!(gen_noise(13, 7, 42, true), gen_noise(13, 7, 42, true));
+    assert_ne!(gen_noise(13, 7, 42, true), gen_noise(13, 7, 43, true));
+    assert!(gen_noise(13, 7, 0, false).iter().all(|px| px.a == 255));
+
+    let pixels = gen_noise(61, 67, 7, true);
+    round_trip(61, 67, QoiChannels::Rgba, |x, y| {
+        pixels[(y * 61 + x) as usize]
+    });
+    let pixels = gen_noise(61, 67, 7, false);
+    round_trip(61, 67, QoiChannels::Rgb, |x, y| {
+        pixels[(y * 61 + x) as usize]
+    });
+}
+
+#[test]
+fn single_pixel() {
+    round_trip(1, 1, QoiChannels::Rgb, |_, _| Pixel::rgb(200, 100, 50));
+    round_trip(1, 1, QoiChannels::Rgba, |_, _| {
+        Pixel::rgba(200, 100, 50, 25)
+    });
+}
+
+#[test]
+fn single_pixel_initial_state() {
+    // the initial previous pixel is (0,0,0,255) and the zeroed index contains (0,0,0,0),
+    // so these pixels are encoded relative to the initial state
+    let cases = [
+        (Pixel::rgb(0, 0, 0), QoiChunk::new_run(1)),
+        (Pixel::ZERO, QoiChunk::new_index(Pixel::ZERO.pixel_hash())),
+        (Pixel::rgb(1, 0, 255), QoiChunk::new_diff(1, 0, -1)),
+        (Pixel::rgb(255, 255, 255), QoiChunk::new_diff(-1, -1, -1)),
+        (Pixel::rgb(200, 100, 50), QoiChunk::new_rgb(200, 100, 50)),
+        (Pixel::rgba(0, 0, 0, 254), QoiChunk::new_rgba(0, 0, 0, 254)),
+    ];
+
+    for channels in [QoiChannels::Rgb, QoiChannels::Rgba] {
+        for (pixel, chunk) in cases.iter().cloned() {
+            let header = QoiHeader::new(1, 1, channels, QoiColorSpace::SRgbWithLinearAlpha);
+            let qoi = QoiEncoder::new(header, core::iter::once(pixel)).collect::<Vec<_>>();
+
+            let mut expected = header.to_bytes().to_vec();
+            expected.extend(chunk);
+            expected.extend(QOI_FOOTER);
+            assert_eq!(qoi, expected, "{pixel:?}");
+
+            round_trip(1, 1, channels, |_, _| pixel);
+        }
+    }
+}
+
+#[test]
+fn checkerboard() {
+    round_trip(63, 65, QoiChannels::Rgb, |x, y| {
+        if (x + y) % 2 == 0 {
+            Pixel::rgb(0, 0, 0)
+        } else {
+            Pixel::rgb(255, 255, 255)
+        }
+    });
+    round_trip(63, 65, QoiChannels::Rgba, |x, y| {
+        if (x / 8 + y / 8) % 2 == 0 {
+            Pixel::rgba(255, 0, 0, 0)
+        } else {
+            Pixel::rgba(0, 0, 255, 255)
+        }
+    });
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn downsample() {
+    let small = (0..12)
+        .map(|i| Pixel::rgba(i, 2 * i, 3 * i, 255 - i))
+        .collect::<Vec<_>>();
+    // every pixel of the 4x3 image repeated 2x2
+    let large = (0..6)
+        .flat_map(|y| (0..8).map(move |x| (x, y)))
+        .map(|(x, y)| small[y / 2 * 4 + x / 2])
+        .collect::<Vec<_>>();
+
+    assert_eq!(downsample_2x_lossless(&large, 8, 6), Ok((4, 3, small)));
+
+    let mut broken = large.clone();
+    broken[3 * 8 + 5] = Pixel::ZERO;
+    assert_eq!(
+        downsample_2x_lossless(&broken, 8, 6),
+        Err(DownsampleError::NonUniformBlock { x: 2, y: 1 })
+    );
+    assert_eq!(
+        downsample_2x_lossless(&large[..8 * 5], 8, 5),
+        Err(DownsampleError::OddSize {
+            width: 8,
+            height: 5
+        })
+    );
+}
+
+#[test]
+#[cfg(feature = "alpha-mask")]
+fn alpha_mask() {
+    use arqoii::mask::{decode_alpha_mask, encode_alpha_mask};
+
+    let mask = (0..48u32 * 32)
+        .map(|idx| match idx % 48 {
+            0..=15 => 0,
+            16..=31 => (idx % 16 * 17) as u8,
+            _ => 255,
+        })
+        .collect::<Vec<_>>();
+
+    let qoi = encode_alpha_mask(48, 32, &mask).collect::<Vec<_>>();
+    let (header, decoded) = decode_alpha_mask(&qoi).unwrap();
+
+    assert_eq!(
+        header,
+        QoiHeader::new(48, 32, QoiChannels::Rgb, QoiColorSpace::AllChannelsLinear)
+    );
+    assert_eq!(decoded, mask);
+}
+
+#[test]
+#[cfg(feature = "alpha-mask")]
+fn alpha_planes() {
+    use arqoii::{
+        decode::decode_fuzz_safe,
+        encode::encode_slice,
+        mask::{decode_alpha_mask, encode_alpha_mask, merge_alpha, split_alpha},
+    };
+
+    let pixels = (0..=255u8)
+        .map(|idx| Pixel::rgba(idx, idx / 16, 7, idx % 16 * 17))
+        .collect::<Vec<_>>();
+
+    let (colors, alpha) = split_alpha(&pixels);
+    assert!(colors.iter().all(|px| px.a == 255));
+
+    let header = QoiHeader::new(16, 16, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha);
+    let color_qoi = encode_slice(header, &colors);
+    let alpha_qoi = encode_alpha_mask(16, 16, &alpha).collect::<Vec<_>>();
+
+    let (_, colors) = decode_fuzz_safe(&color_qoi).unwrap();
+    let (_, alpha) = decode_alpha_mask(&alpha_qoi).unwrap();
+    assert_eq!(merge_alpha(&colors, &alpha), pixels);
+}
+
+#[test]
+fn tiles() {
+    let header = QoiHeader::new(10, 7, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha);
+    let pixels = (0..7u8)
+        .flat_map(|y| (0..10u8).map(move |x| Pixel::rgb(x, y, 0)))
+        .collect::<Vec<_>>();
+
+    let mut seen = vec![0; pixels.len()];
+    let mut count = 0;
+
+    for (tile_x, tile_y, tile_header, tile) in arqoii::tile::tiles(&pixels, header, 4, 3) {
+        count += 1;
+        assert_eq!(tile_header.width, if tile_x == 2 { 2 } else { 4 });
+        assert_eq!(tile_header.height, if tile_y == 2 { 1 } else { 3 });
+
+        let qoi = QoiEncoder::new(tile_header, tile).collect::<Vec<_>>();
+        let (_header, decoder) = QoiDecoder::new(qoi.into_iter()).unwrap();
+        let tile = decoder.collect::<Vec<_>>();
+        assert_eq!(tile.len() as u64, tile_header.pixel_count());
+
+        for (idx, pixel) in tile.iter().enumerate() {
+            let x = tile_x * 4 + idx as u32 % tile_header.width;
+            let y = tile_y * 3 + idx as u32 / tile_header.width;
+            assert_eq!(pixel, &Pixel::rgb(x as u8, y as u8, 0));
+            seen[(y * 10 + x) as usize] += 1;
+        }
+    }
+
+    assert_eq!(count, 3 * 3);
+    assert!(seen.iter().all(|&n| n == 1));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn tiles_from_stream() {
+    let header = QoiHeader::new(10, 7, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha);
+    let pixels = (0..7u8)
+        .flat_map(|y| (0..10u8).map(move |x| Pixel::rgb(x, y, 0)))
+        .collect::<Vec<_>>();
+    let qoi = QoiEncoder::new(header, pixels.iter().copied()).collect::<Vec<_>>();
+
+    let (header, decoder) = QoiDecoder::from_slice(&qoi).unwrap();
+    let streamed = arqoii::tile::tiles_from_stream(decoder, header, 4, 3).collect::<Vec<_>>();
+    let sliced = arqoii::tile::tiles(&pixels, header, 4, 3)
+        .map(|(x, y, header, tile)| (x, y, header, tile.collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    assert_eq!(streamed, sliced);
+
+    // the last row of tiles is incomplete
+    let truncated = arqoii::tile::tiles_from_stream(pixels[..65].iter().copied(), header, 4, 3);
+    assert_eq!(truncated.count(), 2 * 3);
+}
+
+#[test]
+#[cfg(feature = "image")]
+fn image_buffers() {
+    use arqoii::image::{decode_rgb_image, decode_rgba_image, encode_rgb_image, encode_rgba_image};
+
+    let rgba = image::RgbaImage::from_fn(31, 17, |x, y| {
+        image::Rgba([x as u8 * 8, y as u8 * 15, (x ^ y) as u8, (x * y) as u8])
+    });
+    let qoi = encode_rgba_image(&rgba);
+    assert_eq!(decode_rgba_image(&qoi).unwrap(), rgba);
+
+    let rgb = image::RgbImage::from_fn(31, 17, |x, y| image::Rgb([x as u8, y as u8, 42]));
+    let qoi = encode_rgb_image(&rgb);
+    assert_eq!(decode_rgb_image(&qoi).unwrap(), rgb);
+
+    assert!(decode_rgb_image(&qoi[..qoi.len() / 2]).is_err());
+}
+
+fn round_trip(
+    width: u32,
+    height: u32,
+    channels: QoiChannels,
+    mut pixel: impl FnMut(u32, u32) -> Pixel,
+) {
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| pixel(x, y))
+        .collect::<Vec<_>>();
+
+    let header = QoiHeader::new(width, height, channels, QoiColorSpace::SRgbWithLinearAlpha);
+    let qoi = QoiEncoder::new(header, pixels.iter().cloned()).collect::<Vec<_>>();
+
+    let (decoded_header, decoder) = QoiDecoder::new(qoi.into_iter()).unwrap();
+
+    assert_eq!(decoded_header, header);
+    assert!(Iterator::eq(decoder, pixels));
+}
@@ -1,5 +1,9 @@
-use arqoii::{decode::QoiChunkDecoder, encode::QoiChunkEncoder};
-use arqoii_types::{Pixel, QoiChannels, QoiColorSpace, QoiHeader};
+use arqoii::{
+    adapter::PixelIteratorExt,
+    decode::{bytes_to_chunks, count_chunks, DecoderOptions, QoiChunkDecoder, QoiDecoder},
+    encode::{chunks_to_bytes, ChunkPreference, EncoderOptions, QoiChunkEncoder},
+};
+use arqoii_types::{Pixel, QoiChannels, QoiChunk, QoiColorSpace, QoiHeader, QOI_FOOTER};
 
 #[test]
 fn dice() {
@@ -49,6 +53,297 @@ fn wikipedia_008() {
     transcode("qoi/wikipedia_008", None);
 }
 
+#[test]
+fn drop_alpha() {
+    let png_bytes = std::fs::read("tests/test-images/qoi/testcard_rgba.png").unwrap();
+    let (_info, reference_px) = load_png(&png_bytes);
+
+    assert!(reference_px.iter().any(|px| px.a != 255));
+
+    let mut encoder = QoiChunkEncoder::new(reference_px.into_iter().drop_alpha());
+
+    assert!(!encoder.any(|chunk| matches!(chunk, QoiChunk::Rgba { .. })));
+}
+
+#[test]
+fn chunk_bytes_round_trip() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/kodim23.qoi").unwrap();
+    let data = &reference_qoi[14..reference_qoi.len() - 8];
+
+    let chunks = bytes_to_chunks(data.iter().copied()).collect::<Vec<_>>();
+
+    assert!(Iterator::eq(chunks_to_bytes(chunks), data.iter().copied()));
+}
+
+#[test]
+fn empty() {
+    assert_eq!(QoiChunkEncoder::new(std::iter::empty()).next(), None);
+}
+
+#[test]
+fn grayscale_alpha() {
+    // a gray gradient with a semi-transparent stripe, as loaded from a grayscale alpha png
+    let pixels = (0..32u8)
+        .flat_map(|y| (0..64u8).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let c = x * 2 + y;
+            Pixel::rgba(c, c, c, if x < 8 { 128 } else { 255 })
+        })
+        .collect::<Vec<_>>();
+
+    let chunks = QoiChunkEncoder::new(pixels.iter().cloned()).collect::<Vec<_>>();
+
+    let rgba = chunks
+        .iter()
+        .filter(|chunk| matches!(chunk, QoiChunk::Rgba { .. }))
+        .count();
+    let delta = chunks
+        .iter()
+        .filter(|chunk| matches!(chunk, QoiChunk::Diff { .. } | QoiChunk::Luma { .. }))
+        .count();
+
+    // only the alpha changes at the start and the end of the stripe need an Rgba chunk
+    assert_eq!(rgba, 2 * 32);
+    assert!(
+        delta > 10 * rgba,
+        "{delta} delta chunks vs {rgba} rgba chunks"
+    );
+    assert!(!chunks
+        .iter()
+        .any(|chunk| matches!(chunk, QoiChunk::Rgb { .. })));
+}
+
+#[test]
+fn stats() {
+    let png_bytes = std::fs::read("tests/test-images/qoi/qoi_logo.png").unwrap();
+    let (_info, pixels) = load_png(&png_bytes);
+
+    let mut encoder = QoiChunkEncoder::new(pixels.iter().cloned()).with_stats();
+    let bytes = chunks_to_bytes(encoder.by_ref()).collect::<Vec<_>>();
+    let stats = *encoder.stats();
+
+    assert_eq!(stats.pixels, pixels.len() as u64);
+    assert_eq!(stats.bytes, bytes.len() as u64);
+
+    let mut decoder = bytes_to_chunks(bytes).with_stats();
+    assert_eq!(decoder.by_ref().count() as u64, stats.chunks());
+    assert_eq!(decoder.stats(), &stats);
+}
+
+#[test]
+fn run_boundaries() {
+    // the initial previous pixel, so all pixels are part of the run
+    let pixel = Pixel::rgb(0, 0, 0);
+
+    for (count, runs) in [
+        (1, &[1][..]),
+        (61, &[61]),
+        (62, &[62]),
+        (63, &[62, 1]),
+        (64, &[62, 2]),
+        (124, &[62, 62]),
+        (125, &[62, 62, 1]),
+    ] {
+        let chunks = QoiChunkEncoder::new(std::iter::repeat(pixel).take(count)).collect::<Vec<_>>();
+        let expected = runs
+            .iter()
+            .map(|&run| QoiChunk::new_run(run))
+            .collect::<Vec<_>>();
+        assert_eq!(chunks, expected, "{count} pixels");
+    }
+
+    // a run interrupted right after being flushed
+    let pixels = std::iter::repeat(pixel)
+        .take(62)
+        .chain([Pixel::rgb(1, 1, 1)]);
+    assert_eq!(
+        QoiChunkEncoder::new(pixels).collect::<Vec<_>>(),
+        [QoiChunk::new_run(62), QoiChunk::new_diff(1, 1, 1)]
+    );
+}
+
+#[test]
+fn prefer_diff() {
+    let pixels = [
+        Pixel::rgb(10, 10, 10),
+        Pixel::rgb(11, 11, 11),
+        Pixel::rgb(10, 10, 10),
+    ];
+
+    let index_first = QoiChunkEncoder::new(pixels.iter().cloned()).collect::<Vec<_>>();
+    let diff_first = QoiChunkEncoder::with_options(
+        pixels.iter().cloned(),
+        EncoderOptions::new().prefer(ChunkPreference::DiffFirst),
+    )
+    .collect::<Vec<_>>();
+
+    assert_eq!(index_first[2], QoiChunk::new_index(pixels[0].pixel_hash()));
+    assert_eq!(diff_first[2], QoiChunk::new_diff(-1, -1, -1));
+    assert_eq!(index_first[..2], diff_first[..2]);
+
+    let decode = |chunks: Vec<QoiChunk>| {
+        let mut file = QoiHeader::new(3, 1, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha)
+            .to_bytes()
+            .to_vec();
+        file.extend(chunks_to_bytes(chunks));
+        file.extend(QOI_FOOTER);
+        let (_header, decoder) = QoiDecoder::new(file.into_iter()).unwrap();
+        decoder.collect::<Vec<_>>()
+    };
+    assert_eq!(decode(index_first), pixels);
+    assert_eq!(decode(diff_first), pixels);
+}
+
+#[test]
+fn max_run() {
+    let pixels = vec![Pixel::rgb(100, 150, 200); 101];
+
+    let chunks = QoiChunkEncoder::with_options(
+        pixels.iter().cloned(),
+        EncoderOptions::default().max_run(32),
+    )
+    .collect::<Vec<_>>();
+
+    assert_eq!(
+        chunks,
+        [
+            QoiChunk::new_rgb(100, 150, 200),
+            QoiChunk::new_run(32),
+            QoiChunk::new_run(32),
+            QoiChunk::new_run(32),
+            QoiChunk::new_run(4),
+        ]
+    );
+}
+
+#[test]
+fn initial_with_options() {
+    let previous = Pixel::rgb(10, 20, 30);
+    let options = EncoderOptions::new().max_run(4);
+
+    let chunks =
+        QoiChunkEncoder::with_initial_and_options(previous, [previous; 10].into_iter(), options)
+            .collect::<Vec<_>>();
+    assert_eq!(
+        chunks,
+        [
+            QoiChunk::new_run(4),
+            QoiChunk::new_run(4),
+            QoiChunk::new_run(2)
+        ]
+    );
+}
+
+#[test]
+fn header_aware_footer() {
+    let header = QoiHeader::new(9, 1, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+
+    // the first 8 chunks are byte for byte identical to the footer
+    let mut data = vec![];
+    data.extend(QOI_FOOTER);
+    data.push(0x00);
+    data.extend(QOI_FOOTER);
+
+    assert_eq!(QoiChunkDecoder::new(data.iter().copied()).count(), 0);
+
+    let chunks =
+        QoiChunkDecoder::new_from_header_aware(data.iter().copied(), &header).collect::<Vec<_>>();
+    let mut expected = vec![QoiChunk::new_index(0); 7];
+    expected.push(QoiChunk::new_index(1));
+    expected.push(QoiChunk::new_index(0));
+    assert_eq!(chunks, expected);
+
+    let mut file = header.to_bytes().to_vec();
+    file.extend(&data);
+    let (_header, decoder) = QoiDecoder::new_bounded(file.iter().copied()).unwrap();
+    assert_eq!(decoder.collect::<Vec<_>>(), vec![Pixel::ZERO; 9]);
+}
+
+#[test]
+fn display_with_offsets() {
+    let chunks = [
+        QoiChunk::new_rgba(1, 2, 3, 4),
+        QoiChunk::new_diff(-1, 0, 1),
+        QoiChunk::new_luma(-32, 7, 0),
+        QoiChunk::new_rgb(5, 6, 7),
+        QoiChunk::new_index(42),
+        QoiChunk::new_run(15),
+    ];
+
+    let listing = bytes_to_chunks(chunks_to_bytes(chunks.iter().cloned()))
+        .with_offsets()
+        .map(|(offset, chunk)| format!("{offset}: {chunk}"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        listing,
+        [
+            "0: Rgba r=1 g=2 b=3 a=4",
+            "5: Diff dr=-1 dg=0 db=+1",
+            "6: Luma dg=-32 dr-dg=+7 db-dg=0",
+            "8: Rgb r=5 g=6 b=7",
+            "12: Index 42",
+            "13: Run 15",
+        ]
+    );
+}
+
+#[test]
+fn footer_prefixes() {
+    // sequences differing from the footer in any single byte are chunk data
+    for idx in 0..QOI_FOOTER.len() {
+        let mut data = QOI_FOOTER.to_vec();
+        data[idx] ^= 0x02;
+        data.extend(QOI_FOOTER);
+
+        assert_eq!(bytes_to_chunks(data).count(), 8, "byte {idx} changed");
+    }
+
+    // a truncated footer at the end of the data is chunk data as well
+    assert_eq!(bytes_to_chunks(QOI_FOOTER[..7].to_vec()).count(), 7);
+}
+
+#[test]
+fn footer_mid_stream() {
+    // an image whose pixel data contains the footer byte sequence before its end
+    let header = QoiHeader::new(12, 1, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+    let mut chunks = vec![QoiChunk::new_index(0); 7];
+    chunks.push(QoiChunk::new_index(1));
+    chunks.extend([
+        QoiChunk::new_rgba(1, 2, 3, 4),
+        QoiChunk::new_diff(1, 1, 1),
+        QoiChunk::new_run(2),
+    ]);
+
+    let mut file = header.to_bytes().to_vec();
+    file.extend(chunks_to_bytes(chunks.iter().cloned()));
+    assert_eq!(file[14..22], QOI_FOOTER);
+    file.extend(QOI_FOOTER);
+
+    let mut expected = vec![Pixel::ZERO; 8];
+    expected.push(Pixel::rgba(1, 2, 3, 4));
+    expected.extend(vec![Pixel::rgba(2, 3, 4, 4); 3]);
+
+    let (_header, decoder) = QoiDecoder::new_bounded(file.iter().copied()).unwrap();
+    assert_eq!(decoder.collect::<Vec<_>>(), expected);
+
+    #[cfg(feature = "alloc")]
+    {
+        let (_header, decoded) = arqoii::decode::decode_fuzz_safe(&file).unwrap();
+        assert_eq!(decoded, expected);
+    }
+
+    let options = DecoderOptions::new()
+        .enforce_pixel_count(true)
+        .require_footer(true);
+    let (_header, decoder) = QoiDecoder::with_options(file.iter().copied(), options).unwrap();
+    assert_eq!(decoder.finish(), Ok(()));
+
+    // without knowing the pixel count the footer is detected prematurely
+    let (_header, decoder) = QoiDecoder::new(file.iter().copied()).unwrap();
+    assert_eq!(decoder.count(), 0);
+}
+
 fn transcode(name: &str, _alt_header: Option<QoiHeader>) {
     let reference_qoi = std::fs::read(format!("tests/test-images/{name}.qoi")).unwrap();
     let png_bytes = std::fs::read(format!("tests/test-images/{name}.png")).unwrap();
@@ -109,3 +404,73 @@ fn load_png(data: &[u8]) -> (OutputInfo, Vec<Pixel>) {
 
     (info, result)
 }
+
+#[test]
+fn count() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let chunk_data = &reference_qoi[14..];
+
+    assert_eq!(
+        count_chunks(chunk_data),
+        bytes_to_chunks(chunk_data.iter().copied())
+            .collect::<Vec<_>>()
+            .len()
+    );
+    assert_eq!(count_chunks(&QOI_FOOTER), 0);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn raw_bytes() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let (header, _) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let chunk_data = &reference_qoi[14..reference_qoi.len() - QOI_FOOTER.len()];
+
+    let mut raw_data = Vec::new();
+    for (chunk, raw) in
+        QoiChunkDecoder::new_from_header_aware(chunk_data.iter().copied(), &header).with_raw_bytes()
+    {
+        assert_eq!(raw.len(), chunk.encoded_len());
+        assert_eq!(raw, chunk.into_iter().collect::<Vec<_>>());
+        raw_data.extend(raw);
+    }
+    assert_eq!(raw_data, chunk_data);
+}
+
+#[test]
+fn peek_chunk() {
+    let pixels = [
+        Pixel::rgb(10, 20, 30),
+        Pixel::rgb(10, 20, 30),
+        Pixel::rgb(11, 20, 30),
+        Pixel::rgba(1, 2, 3, 4),
+    ];
+    let expected = QoiChunkEncoder::new(pixels.into_iter()).collect::<Vec<_>>();
+
+    let mut encoder = QoiChunkEncoder::new(pixels.into_iter());
+    let mut chunks = vec![];
+    while let Some(peeked) = encoder.peek_chunk().cloned() {
+        // peeking again does not advance
+        assert_eq!(encoder.peek_chunk(), Some(&peeked));
+        assert_eq!(encoder.next().as_ref(), Some(&peeked));
+        chunks.push(peeked);
+    }
+    assert_eq!(encoder.next(), None);
+    assert_eq!(chunks, expected);
+}
+
+#[test]
+fn strict_channels() {
+    let pixels = [Pixel::rgba(100, 150, 200, 4)];
+    let strict = EncoderOptions::new().strict_channels(true);
+
+    let chunks = QoiChunkEncoder::with_options(pixels.into_iter(), strict.clone())
+        .channels(QoiChannels::Rgb)
+        .collect::<Vec<_>>();
+    assert_eq!(chunks, [QoiChunk::new_rgb(100, 150, 200)]);
+
+    let chunks = QoiChunkEncoder::with_options(pixels.into_iter(), strict)
+        .channels(QoiChannels::Rgba)
+        .collect::<Vec<_>>();
+    assert_eq!(chunks, [QoiChunk::new_rgba(100, 150, 200, 4)]);
+}
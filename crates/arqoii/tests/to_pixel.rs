@@ -1,5 +1,12 @@
-use arqoii::decode::QoiDecoder;
-use arqoii_types::{Pixel, QoiChannels, QoiColorSpace, QoiHeader};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "alloc")]
+use arqoii::decode::{decode_fuzz_safe, PushDecoder};
+use arqoii::decode::{DecodeError, DecoderOptions, IndexCheck, QoiDecoder, CANCEL_CHECK_INTERVAL};
+use arqoii::digest::pixel_digest;
+use arqoii::encode::{chunks_to_bytes, EncoderOptions, QoiChunkEncoder};
+use arqoii::QOI_FOOTER;
+use arqoii_types::{Pixel, QoiChannels, QoiChunk, QoiColorSpace, QoiHeader};
 
 #[test]
 fn dice() {
@@ -49,6 +56,387 @@ fn wikipedia_008() {
     transcode("qoi/wikipedia_008", None);
 }
 
+#[test]
+fn bounded_count() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+
+    let (header, decoder) = QoiDecoder::new_bounded(reference_qoi.iter().copied()).unwrap();
+    assert_eq!(decoder.count() as u64, header.pixel_count());
+
+    let (_header, mut decoder) = QoiDecoder::new_bounded(reference_qoi.iter().copied()).unwrap();
+    decoder.nth(41);
+    assert_eq!(
+        decoder.remaining_declared(),
+        Some(header.pixel_count() - 42)
+    );
+    assert_eq!(decoder.count() as u64, header.pixel_count() - 42);
+
+    // the data ends long before the declared pixels, count does not take the header's word for it
+    let header = QoiHeader::new(
+        1000,
+        1000,
+        QoiChannels::Rgb,
+        QoiColorSpace::SRgbWithLinearAlpha,
+    );
+    let qoi = [header.to_bytes().as_slice(), &[0xc0 | (9 - 1)], &QOI_FOOTER].concat();
+    let (_header, decoder) = QoiDecoder::new_bounded(qoi.iter().copied()).unwrap();
+    assert_eq!(decoder.remaining_declared(), Some(1_000_000));
+    let decoded = decoder.collect::<Vec<_>>().len();
+    assert!(decoded < 1_000_000);
+    let (_header, decoder) = QoiDecoder::new_bounded(qoi.iter().copied()).unwrap();
+    assert_eq!(decoder.count(), decoded);
+}
+
+#[test]
+fn nth() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let (_header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    for n in [0, 1, 61, 62, 63, 1000, pixels.len() - 2] {
+        let (_header, mut decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+        assert_eq!(decoder.nth(n), pixels.get(n).cloned(), "nth({n})");
+        assert_eq!(
+            decoder.next(),
+            pixels.get(n + 1).cloned(),
+            "next after nth({n})"
+        );
+    }
+}
+
+#[test]
+fn fold() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let (_header, mut decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = core::iter::from_fn(|| decoder.next()).collect::<Vec<_>>();
+
+    for skip in [0, 1, 61, 1000] {
+        let (_header, mut decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+        if skip > 0 {
+            decoder.nth(skip - 1);
+        }
+        let mut folded = vec![];
+        decoder.for_each(|px| folded.push(px));
+        assert_eq!(folded, pixels[skip..], "fold after skipping {skip}");
+    }
+}
+
+#[test]
+fn progress() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let mut reports = vec![];
+
+    let (header, decoder) =
+        QoiDecoder::new_with_progress(reference_qoi.iter().copied(), |done, total| {
+            reports.push((done, total))
+        })
+        .unwrap();
+    let total = header.pixel_count();
+    decoder.interval(100_000).for_each(drop);
+
+    let expected = (1..=total / 100_000)
+        .map(|step| (step * 100_000, total))
+        .chain([(total, total)])
+        .collect::<Vec<_>>();
+    assert_eq!(reports, expected);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn fuzz_safe() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+
+    assert_eq!(decode_fuzz_safe(&[]), Err(DecodeError::UnexpectedEof));
+    assert_eq!(
+        decode_fuzz_safe(b"qoig"),
+        Err(DecodeError::InvalidMagic(*b"qoig"))
+    );
+
+    for len in (0..reference_qoi.len()).step_by(97) {
+        let _ = decode_fuzz_safe(&reference_qoi[..len]);
+    }
+
+    let mut corrupted = reference_qoi.clone();
+    let mut seed = 0x2545_f491_u32;
+    for byte in corrupted.iter_mut().skip(14) {
+        // xorshift32
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        *byte = seed as u8;
+    }
+    let (header, pixels) = decode_fuzz_safe(&corrupted).unwrap();
+    assert!(pixels.len() as u64 <= header.pixel_count());
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn push_decoder() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/testcard_rgba.qoi").unwrap();
+    let (_header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let expected = decoder.collect::<Vec<_>>();
+
+    let mut push_decoder = PushDecoder::new();
+    let mut pixels = vec![];
+    for slice in reference_qoi.chunks(3) {
+        push_decoder.feed(slice).unwrap();
+        pixels.extend(push_decoder.by_ref());
+    }
+
+    assert!(push_decoder.is_finished());
+    assert_eq!(push_decoder.header().unwrap().width, 256);
+    assert_eq!(pixels, expected);
+}
+
+#[test]
+fn finish() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/kodim10.qoi").unwrap();
+
+    let (_header, mut decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    decoder.nth(1000);
+    assert_eq!(decoder.finish(), Ok(()));
+
+    let truncated = &reference_qoi[..reference_qoi.len() / 2];
+    let (header, decoder) = QoiDecoder::new(truncated.iter().copied()).unwrap();
+    assert!(matches!(
+        decoder.finish(),
+        Err(DecodeError::PixelCountMismatch { expected, found }) if expected == header.pixel_count() && found < expected
+    ));
+}
+
+#[test]
+fn into_parts() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    assert_eq!(decoder.header(), &header);
+
+    let (parts_header, pixels) = decoder.into_parts();
+    assert_eq!(parts_header, header);
+    assert_eq!(pixels.count() as u64, header.pixel_count());
+}
+
+#[test]
+fn digest() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    // a different encoding of the same pixels has the same digest
+    let mut reencoded = header.to_bytes().to_vec();
+    reencoded.extend(chunks_to_bytes(QoiChunkEncoder::with_options(
+        pixels.iter().cloned(),
+        EncoderOptions::default().max_run(7),
+    )));
+    reencoded.extend(QOI_FOOTER);
+    assert_ne!(reencoded, reference_qoi);
+    let (_header, decoder) = QoiDecoder::new(reencoded.iter().copied()).unwrap();
+    assert_eq!(pixel_digest(decoder), pixel_digest(pixels.iter().cloned()));
+
+    let mut changed = pixels.clone();
+    changed[0].r ^= 1;
+    assert_ne!(pixel_digest(changed), pixel_digest(pixels.iter().cloned()));
+
+    // hashing while decoding
+    let (_header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let mut decoder = decoder.with_digest();
+    assert_eq!(decoder.by_ref().count(), pixels.len());
+    assert_eq!(decoder.digest(), pixel_digest(pixels.iter().cloned()));
+
+    // FNV-1a of no data is the offset basis
+    assert_eq!(pixel_digest([]), 0xcbf29ce484222325);
+}
+
+#[test]
+fn trailing_bytes() {
+    let mut data = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let metadata = br#"{"tool":"test"}"#;
+    data.extend(metadata);
+
+    let (_header, mut decoder) = QoiDecoder::new(data.iter().copied()).unwrap();
+    decoder.by_ref().for_each(drop);
+    assert!(decoder
+        .trailing_bytes()
+        .unwrap()
+        .eq(metadata.iter().copied()));
+
+    let (_header, mut decoder) = QoiDecoder::new_bounded(data.iter().copied()).unwrap();
+    decoder.by_ref().for_each(drop);
+    assert!(decoder
+        .trailing_bytes()
+        .unwrap()
+        .eq(metadata.iter().copied()));
+
+    // not done decoding yet
+    let (_header, mut decoder) = QoiDecoder::new_bounded(data.iter().copied()).unwrap();
+    decoder.next();
+    assert!(decoder.trailing_bytes().is_none());
+}
+
+#[test]
+fn options() {
+    let strict = DecoderOptions::new()
+        .enforce_pixel_count(true)
+        .require_footer(true)
+        .reject_redundant_index(true)
+        .reject_bad_run(true)
+        .check_unwritten_index(IndexCheck::Error);
+
+    let reference_qoi = std::fs::read("tests/test-images/qoi/kodim23.qoi").unwrap();
+    let (_header, decoder) =
+        QoiDecoder::with_options(reference_qoi.iter().copied(), strict.clone()).unwrap();
+    assert_eq!(decoder.finish(), Ok(()));
+
+    let qoi = |width, chunks: &[QoiChunk], footer: bool| {
+        let header = QoiHeader::new(
+            width,
+            1,
+            QoiChannels::Rgba,
+            QoiColorSpace::AllChannelsLinear,
+        );
+        let mut qoi = header.to_bytes().to_vec();
+        qoi.extend(chunks.iter().flatten());
+        if footer {
+            qoi.extend(QOI_FOOTER);
+        }
+        qoi
+    };
+    let decode = |qoi: Vec<u8>| {
+        let (_header, decoder) = QoiDecoder::with_options(qoi.into_iter(), strict.clone()).unwrap();
+        decoder.finish()
+    };
+
+    let pixel = Pixel::rgba(1, 2, 3, 4);
+    let rgba = QoiChunk::new_rgba(1, 2, 3, 4);
+    let index = QoiChunk::new_index(pixel.pixel_hash());
+
+    assert_eq!(
+        decode(qoi(3, &[rgba.clone(), index.clone(), index.clone()], true)),
+        Err(DecodeError::RedundantIndex(pixel.pixel_hash()))
+    );
+    assert_eq!(
+        decode(qoi(3, &[rgba.clone(), QoiChunk::new_run(5)], true)),
+        Err(DecodeError::RunExceedsImage {
+            run: 5,
+            remaining: 2
+        })
+    );
+    assert_eq!(
+        decode(qoi(2, &[rgba.clone(), index.clone()], false)),
+        Err(DecodeError::MissingFooter)
+    );
+    assert_eq!(
+        decode(qoi(2, &[index.clone(), rgba.clone()], true)),
+        Err(DecodeError::UnwrittenIndex(pixel.pixel_hash()))
+    );
+    // the zeroed index contains Pixel::ZERO
+    let zero = QoiChunk::new_index(Pixel::ZERO.pixel_hash());
+    assert_eq!(decode(qoi(2, &[zero, rgba.clone()], true)), Ok(()));
+
+    let warn = DecoderOptions::new().check_unwritten_index(IndexCheck::Warn);
+    let mut decoder = QoiDecoder::with_options(
+        qoi(3, &[index.clone(), rgba.clone(), index.clone()], true).into_iter(),
+        warn,
+    )
+    .unwrap()
+    .1;
+    assert_eq!(decoder.by_ref().count(), 3);
+    assert_eq!(decoder.unwritten_index_count(), 1);
+
+    // the default options are lenient
+    let (_header, decoder) =
+        QoiDecoder::new(qoi(3, &[rgba.clone(), index.clone(), index], true).into_iter()).unwrap();
+    assert_eq!(decoder.finish(), Ok(()));
+}
+
+#[test]
+fn huge_pixel_count() {
+    // width * height exceeds u32::MAX
+    let header = QoiHeader::new(
+        100_000,
+        100_000,
+        QoiChannels::Rgb,
+        QoiColorSpace::SRgbWithLinearAlpha,
+    );
+    assert_eq!(header.pixel_count(), 10_000_000_000);
+
+    let mut qoi = header.to_bytes().to_vec();
+    qoi.extend(
+        [QoiChunk::new_rgb(1, 2, 3), QoiChunk::new_run(62)]
+            .into_iter()
+            .flatten(),
+    );
+    qoi.extend(QOI_FOOTER);
+
+    let (_header, mut decoder) = QoiDecoder::new_bounded(qoi.iter().copied()).unwrap();
+    decoder.next();
+    assert_eq!(
+        decoder.size_hint().1,
+        usize::try_from(9_999_999_999u64).ok()
+    );
+
+    let (_header, decoder) = QoiDecoder::new(qoi.iter().copied()).unwrap();
+    assert_eq!(
+        decoder.finish(),
+        Err(DecodeError::PixelCountMismatch {
+            expected: 10_000_000_000,
+            found: 63
+        })
+    );
+}
+
+#[test]
+fn with_coords() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    let (_header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    for (idx, ((x, y), pixel)) in decoder.with_coords().enumerate() {
+        assert_eq!(x, idx as u32 % header.width);
+        assert_eq!(y, idx as u32 / header.width);
+        assert_eq!(pixel, pixels[idx]);
+    }
+
+    // continuing after some pixels, including some in the middle of a run
+    let (_header, mut decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    decoder.nth(header.width as usize + 9);
+    let ((x, y), pixel) = decoder.with_coords().next().unwrap();
+    assert_eq!((x, y), (10, 1));
+    assert_eq!(pixel, pixels[header.width as usize + 10]);
+}
+
+#[test]
+fn decode_into() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/edgecase.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    let mut out = vec![0; header.pixel_count() as usize * 4 + 3];
+    let (_header, mut decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    assert_eq!(decoder.decode_rgba_into(&mut out), pixels.len());
+    let expected = pixels
+        .iter()
+        .flat_map(|px| [px.r, px.g, px.b, px.a])
+        .collect::<Vec<_>>();
+    assert_eq!(out[..expected.len()], expected);
+
+    // in two steps
+    let (_header, mut decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let (first, second) = out.split_at_mut(4 * 100);
+    assert_eq!(decoder.decode_rgba_premultiplied_into(first), 100);
+    assert_eq!(
+        decoder.decode_rgba_premultiplied_into(second),
+        pixels.len() - 100
+    );
+    let expected = pixels
+        .iter()
+        .map(Pixel::to_premultiplied)
+        .flat_map(|px| [px.r, px.g, px.b, px.a])
+        .collect::<Vec<_>>();
+    assert_eq!(out[..expected.len()], expected);
+}
+
 fn transcode(name: &str, alt_header: Option<QoiHeader>) {
     let reference_qoi = std::fs::read(format!("tests/test-images/{name}.qoi")).unwrap();
     let png_bytes = std::fs::read(format!("tests/test-images/{name}.png")).unwrap();
@@ -125,3 +513,270 @@ fn load_png(data: &[u8]) -> (OutputInfo, Vec<Pixel>) {
 
     (info, result)
 }
+
+#[test]
+fn validate() {
+    for image in [
+        "dice",
+        "edgecase",
+        "kodim10",
+        "kodim23",
+        "qoi_logo",
+        "testcard_rgba",
+        "testcard",
+        "wikipedia_008",
+    ] {
+        let reference_qoi = std::fs::read(format!("tests/test-images/qoi/{image}.qoi")).unwrap();
+        let (header, _) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+        assert_eq!(
+            QoiDecoder::validate(reference_qoi.iter().copied()),
+            Ok(header),
+            "{image}"
+        );
+    }
+
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    assert_eq!(
+        QoiDecoder::validate(reference_qoi[..reference_qoi.len() - 1].iter().copied()),
+        Err(DecodeError::MissingFooter)
+    );
+    assert_eq!(
+        QoiDecoder::validate(reference_qoi[..10].iter().copied()),
+        Err(DecodeError::UnexpectedEof)
+    );
+}
+
+#[test]
+fn cancel() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/kodim23.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    let cancel = AtomicBool::new(false);
+    let (_, mut decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let mut decoded = Vec::new();
+    assert_eq!(
+        decoder.decode_cancellable(&cancel, |px| decoded.push(px)),
+        Ok(())
+    );
+    assert_eq!(decoded, pixels);
+
+    let (_, mut decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let mut decoded = Vec::new();
+    let result = decoder.decode_cancellable(&cancel, |px| {
+        decoded.push(px);
+        if decoded.len() == 10 {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    });
+    assert_eq!(result, Err(DecodeError::Cancelled));
+    // the flag is only checked periodically
+    assert_eq!(decoded.len(), CANCEL_CHECK_INTERVAL);
+    assert!(header.pixel_count() > CANCEL_CHECK_INTERVAL as u64);
+
+    // the decoder can be resumed after a cancellation
+    decoded.extend(decoder);
+    assert_eq!(decoded, pixels);
+}
+
+#[test]
+fn bytes_consumed() {
+    let dice = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let logo = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let stream = [dice.as_slice(), logo.as_slice()].concat();
+
+    let (_, mut decoder) = QoiDecoder::from_slice(&stream).unwrap();
+    assert_eq!(decoder.bytes_consumed(), 14);
+    decoder.by_ref().for_each(drop);
+    assert_eq!(decoder.bytes_consumed(), dice.len());
+
+    // the next image starts right after the first one
+    let (_, decoder) = QoiDecoder::from_slice(&stream[dice.len()..]).unwrap();
+    let (_, reference) = QoiDecoder::from_slice(&logo).unwrap();
+    assert!(decoder.eq(reference));
+
+    // a bounded decoder stops before the footer
+    let (_, mut decoder) = QoiDecoder::new_bounded(stream.iter().copied()).unwrap();
+    decoder.by_ref().for_each(drop);
+    assert_eq!(decoder.bytes_consumed(), dice.len() - QOI_FOOTER.len());
+}
+
+#[test]
+fn channel_mismatch() {
+    let qoi = |channels| {
+        let mut qoi = QoiHeader::new(2, 1, channels, QoiColorSpace::SRgbWithLinearAlpha)
+            .to_bytes()
+            .to_vec();
+        qoi.extend(chunks_to_bytes([
+            QoiChunk::new_rgb(1, 2, 3),
+            QoiChunk::new_rgba(1, 2, 3, 4),
+        ]));
+        qoi.extend(QOI_FOOTER);
+        qoi
+    };
+    let options = DecoderOptions::new().warn_channel_mismatch(true);
+
+    let (_, mut decoder) =
+        QoiDecoder::with_options(qoi(QoiChannels::Rgb).into_iter(), options.clone()).unwrap();
+    // the alpha channel is decoded despite the header
+    assert_eq!(
+        decoder.by_ref().collect::<Vec<_>>(),
+        [Pixel::rgb(1, 2, 3), Pixel::rgba(1, 2, 3, 4)]
+    );
+    assert!(decoder.channel_mismatch());
+    assert_eq!(decoder.finish(), Ok(()));
+
+    let (_, mut decoder) =
+        QoiDecoder::with_options(qoi(QoiChannels::Rgba).into_iter(), options).unwrap();
+    decoder.by_ref().for_each(drop);
+    assert!(!decoder.channel_mismatch());
+
+    let (_, mut decoder) = QoiDecoder::new(qoi(QoiChannels::Rgb).into_iter()).unwrap();
+    decoder.by_ref().for_each(drop);
+    assert!(!decoder.channel_mismatch());
+
+    let reject = DecoderOptions::new().reject_alpha_in_rgb(true);
+    let (_, decoder) =
+        QoiDecoder::with_options(qoi(QoiChannels::Rgb).into_iter(), reject.clone()).unwrap();
+    assert_eq!(
+        decoder.finish(),
+        Err(DecodeError::AlphaInRgbImage { alpha: 4 })
+    );
+    let (_, decoder) =
+        QoiDecoder::with_options(qoi(QoiChannels::Rgba).into_iter(), reject).unwrap();
+    assert_eq!(decoder.finish(), Ok(()));
+}
+
+#[test]
+fn clamp_to_header() {
+    let header = QoiHeader::new(4, 4, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha);
+    // 16 distinct pixels followed by a run crossing the end of the image and one more pixel
+    let pixels = (0..16u8)
+        .map(|v| Pixel::rgb(v * 10, 0, 0))
+        .chain([Pixel::rgb(150, 0, 0); 5])
+        .chain([Pixel::rgb(1, 2, 3)])
+        .collect::<Vec<_>>();
+    let clamp = DecoderOptions::new().clamp_to_header(true);
+
+    for len in [16, 18, 22] {
+        let qoi = arqoii::encode::QoiEncoder::new(header, pixels[..len].iter().copied())
+            .collect::<Vec<_>>();
+
+        let (_, mut decoder) =
+            QoiDecoder::with_options(qoi.iter().copied(), clamp.clone()).unwrap();
+        let decoded = decoder.by_ref().collect::<Vec<_>>();
+        assert_eq!(decoded, pixels[..16], "{len} pixels");
+        assert_eq!(decoder.extra_data(), len > 16, "{len} pixels");
+        assert_eq!(decoder.bytes_consumed(), qoi.len(), "{len} pixels");
+        assert_eq!(decoder.finish(), Ok(()), "{len} pixels");
+
+        let (_, decoder) = QoiDecoder::with_options(qoi.iter().copied(), clamp.clone()).unwrap();
+        let mut folded = vec![];
+        decoder.for_each(|px| folded.push(px));
+        assert_eq!(folded, pixels[..16], "fold {len} pixels");
+
+        let (_, decoder) = QoiDecoder::with_options(qoi.iter().copied(), clamp.clone()).unwrap();
+        let runs = decoder.decode_run_aware().collect::<Vec<_>>();
+        assert_eq!(
+            runs.iter().map(|(_, count)| count).sum::<u32>(),
+            16,
+            "run aware {len} pixels"
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn region() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+    let at = |x: u32, y: u32| pixels[(y * header.width + x) as usize];
+    let fill = Pixel::rgba(1, 2, 3, 4);
+
+    let (_, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let region = decoder.decode_region(100, 50, 3, 2, fill).unwrap();
+    assert_eq!(
+        region,
+        [
+            at(100, 50),
+            at(101, 50),
+            at(102, 50),
+            at(100, 51),
+            at(101, 51),
+            at(102, 51)
+        ]
+    );
+
+    // the bottom right corner extends beyond the image
+    let (x, y) = (header.width - 1, header.height - 1);
+    let (_, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let region = decoder.decode_region(x, y, 2, 2, fill).unwrap();
+    assert_eq!(region, [at(x, y), fill, fill, fill]);
+
+    // completely outside
+    let (_, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    assert_eq!(
+        decoder.decode_region(header.width, 0, 2, 1, fill),
+        Some(vec![fill, fill])
+    );
+
+    // too large to allocate
+    let (_, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    assert_eq!(decoder.decode_region(0, 0, u32::MAX, u32::MAX, fill), None);
+}
+
+#[test]
+fn snapshot_resume() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/kodim10.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    // decode in steps, only keeping the snapshot and offset in between
+    let (_, mut decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let mut decoded = decoder.by_ref().take(1000).collect::<Vec<_>>();
+    let mut state = decoder.snapshot();
+    let mut offset = decoder.bytes_consumed();
+
+    while decoded.len() < pixels.len() {
+        let mut decoder =
+            QoiDecoder::resume(header, state, reference_qoi[offset..].iter().copied());
+        decoded.extend(decoder.by_ref().take(12345));
+        state = decoder.snapshot();
+        offset += decoder.bytes_consumed();
+    }
+
+    assert_eq!(decoded, pixels);
+    // the last step ran out of pixels and stopped at the footer
+    assert_eq!(offset, reference_qoi.len());
+}
+
+#[test]
+fn run_aware() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let (_, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    let (_, mut decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    // start in the middle of a run
+    let mut decoded = decoder.by_ref().take(3).collect::<Vec<_>>();
+    let runs = decoder.decode_run_aware().collect::<Vec<_>>();
+    assert!(runs.iter().any(|(_, count)| *count > 1));
+    for (pixel, count) in runs {
+        decoded.extend(std::iter::repeat(pixel).take(count as usize));
+    }
+    assert_eq!(decoded, pixels);
+
+    // a bounded decoder cuts a run at the end of the image
+    let header = QoiHeader::new(3, 1, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+    let mut qoi = header.to_bytes().to_vec();
+    qoi.extend(chunks_to_bytes([
+        QoiChunk::new_rgb(1, 2, 3),
+        QoiChunk::new_run(5),
+    ]));
+    let (_, decoder) = QoiDecoder::new_bounded(qoi.into_iter()).unwrap();
+    assert_eq!(
+        decoder.decode_run_aware().collect::<Vec<_>>(),
+        [(Pixel::rgb(1, 2, 3), 1), (Pixel::rgb(1, 2, 3), 2)]
+    );
+}
@@ -1,4 +1,16 @@
-use arqoii::encode::QoiEncoder;
+use arqoii::adapter::fit_to_header;
+use arqoii::decode::QoiDecoder;
+#[cfg(feature = "alloc")]
+use arqoii::decode::{decode_to_bgra_bytes, DecodeError};
+#[cfg(feature = "alloc")]
+use arqoii::encode::{
+    chunks_to_bytes, encode_bgra_bytes, encode_into_vec, encode_slice, encode_to_vec,
+    max_output_len, QoiChunkEncoder,
+};
+use arqoii::encode::{
+    encode_split, estimate_qoi_size, EncoderOptions, PushEncoder, QoiEncoder, TryQoiEncoder,
+};
+use arqoii::QOI_FOOTER;
 use arqoii_types::{Pixel, QoiChannels, QoiColorSpace, QoiHeader};
 
 #[test]
@@ -54,6 +66,229 @@ fn wikipedia_008() {
     transcode("qoi/wikipedia_008", None);
 }
 
+#[test]
+fn no_footer() {
+    let pixels = [
+        Pixel::rgb(1, 2, 3),
+        Pixel::rgb(1, 2, 3),
+        Pixel::rgb(4, 5, 6),
+    ];
+    let header = QoiHeader::new(3, 1, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha);
+
+    let with_footer = QoiEncoder::new(header, pixels.iter().cloned()).collect::<Vec<_>>();
+    let without_footer =
+        QoiEncoder::new_no_footer(header, pixels.iter().cloned()).collect::<Vec<_>>();
+
+    assert_eq!(with_footer[..with_footer.len() - 8], without_footer);
+
+    let (_header, decoder) = QoiDecoder::new_bounded(without_footer.into_iter()).unwrap();
+    assert!(Iterator::eq(decoder, pixels));
+}
+
+#[test]
+fn empty() {
+    let header = QoiHeader::new(0, 0, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+
+    let qoi = QoiEncoder::new(header, std::iter::empty()).collect::<Vec<_>>();
+
+    assert_eq!(qoi.len(), 22);
+    assert_eq!(qoi[..14], header.to_bytes());
+    assert_eq!(qoi[14..], QOI_FOOTER);
+
+    let (decoded_header, decoder) = QoiDecoder::new(qoi.into_iter()).unwrap();
+    assert_eq!(decoded_header, header);
+    assert_eq!(decoder.finish(), Ok(()));
+}
+
+#[test]
+fn estimate_size() {
+    for name in ["dice", "qoi_logo", "testcard_rgba"] {
+        let png_bytes = std::fs::read(format!("tests/test-images/qoi/{name}.png")).unwrap();
+        let (info, pixels) = load_png(&png_bytes);
+        let channels = match info.color_type {
+            png::ColorType::Rgb => QoiChannels::Rgb,
+            _ => QoiChannels::Rgba,
+        };
+        let header = QoiHeader::new(
+            info.width,
+            info.height,
+            channels,
+            QoiColorSpace::SRgbWithLinearAlpha,
+        );
+
+        let qoi = QoiEncoder::new(header, pixels.iter().cloned()).count();
+        assert_eq!(estimate_qoi_size(pixels, channels), qoi, "{name}");
+    }
+
+    // an Rgb header does not stop the encoder from encoding transparent pixels
+    let header = QoiHeader::new(3, 1, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha);
+    let pixels = [
+        Pixel::rgba(10, 20, 30, 0),
+        Pixel::rgba(10, 20, 30, 128),
+        Pixel::rgb(10, 20, 30),
+    ];
+    assert_eq!(
+        estimate_qoi_size(pixels, QoiChannels::Rgb),
+        QoiEncoder::new(header, pixels.into_iter()).count()
+    );
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn split_stream() {
+    let files = ["dice", "qoi_logo", "edgecase"]
+        .map(|name| std::fs::read(format!("tests/test-images/qoi/{name}.qoi")).unwrap());
+
+    let stream = files.concat();
+    let split = arqoii::split::split_qoi_stream(stream).collect::<Vec<_>>();
+    assert_eq!(split, files);
+
+    assert_eq!(arqoii::split::split_qoi_stream([]).count(), 0);
+}
+
+#[test]
+fn options() {
+    let pixels = [
+        Pixel::rgba(1, 2, 3, 4),
+        Pixel::rgba(1, 2, 3, 4),
+        Pixel::rgba(4, 5, 6, 255),
+    ];
+    let header = QoiHeader::new(3, 1, QoiChannels::Rgb, QoiColorSpace::SRgbWithLinearAlpha);
+
+    let default = QoiEncoder::with_options(header, pixels.iter().cloned(), EncoderOptions::new())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        default,
+        QoiEncoder::new(header, pixels.iter().cloned()).collect::<Vec<_>>()
+    );
+
+    let strict = QoiEncoder::with_options(
+        header,
+        pixels.iter().cloned(),
+        EncoderOptions::new().strict_channels(true).footer(false),
+    )
+    .collect::<Vec<_>>();
+    assert!(!strict.ends_with(&QOI_FOOTER));
+
+    let (_header, decoder) = QoiDecoder::new_bounded(strict.into_iter()).unwrap();
+    assert!(Iterator::eq(
+        decoder,
+        [
+            Pixel::rgb(1, 2, 3),
+            Pixel::rgb(1, 2, 3),
+            Pixel::rgb(4, 5, 6),
+        ]
+    ));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn recanonicalize() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/qoi_logo.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    // split all runs into single pixel runs
+    let mut non_canonical = header.to_bytes().to_vec();
+    non_canonical.extend(chunks_to_bytes(QoiChunkEncoder::with_options(
+        pixels.iter().cloned(),
+        EncoderOptions::new().max_run(1),
+    )));
+    non_canonical.extend(QOI_FOOTER);
+
+    let canonical = arqoii::transcode::recanonicalize(&non_canonical).unwrap();
+    let expected = QoiEncoder::new(header, pixels.into_iter()).collect::<Vec<_>>();
+    assert_eq!(canonical, expected);
+
+    // drop the footer and some chunks
+    let truncated = &non_canonical[..non_canonical.len() - QOI_FOOTER.len() - 100];
+    assert!(matches!(
+        arqoii::transcode::recanonicalize(truncated),
+        Err(DecodeError::PixelCountMismatch { .. })
+    ));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn reuse_vec() {
+    let header = QoiHeader::new(2, 2, QoiChannels::Rgba, QoiColorSpace::AllChannelsLinear);
+    let pixels = [
+        Pixel::rgba(1, 2, 3, 4),
+        Pixel::rgba(50, 60, 70, 80),
+        Pixel::rgba(90, 100, 110, 120),
+        Pixel::rgba(130, 140, 150, 160),
+    ];
+
+    // the worst case, every pixel needs an Rgba Chunk
+    let max_len = max_output_len(&header).unwrap();
+    assert_eq!(max_len, 14 + 4 * 5 + 8);
+
+    let qoi = encode_to_vec(header, pixels.iter().cloned());
+    assert_eq!(qoi.len(), max_len);
+    assert_eq!(
+        qoi,
+        QoiEncoder::new(header, pixels.iter().cloned()).collect::<Vec<_>>()
+    );
+
+    let mut out = vec![0xff; 100];
+    encode_into_vec(header, pixels.iter().cloned(), &mut out);
+    assert_eq!(out, qoi);
+
+    let capacity = out.capacity();
+    encode_into_vec(header, pixels, &mut out);
+    assert_eq!(out, qoi);
+    assert_eq!(out.capacity(), capacity);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn qoi_to_qoi() {
+    let header = QoiHeader::new(4, 1, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+    let pixels = [
+        Pixel::rgba(10, 20, 30, 255),
+        Pixel::rgba(10, 20, 30, 128),
+        Pixel::rgba(10, 20, 30, 0),
+        Pixel::rgba(40, 50, 60, 255),
+    ];
+    let qoi = QoiEncoder::new(header, pixels.iter().cloned()).collect::<Vec<_>>();
+
+    let (rgba, summary) = arqoii::transcode::transcode(&qoi, QoiChannels::Rgba).unwrap();
+    assert_eq!(rgba, qoi);
+    assert!(!summary.alpha_dropped);
+    assert_eq!(summary.pixels_changed, 0);
+
+    let (rgb, summary) = arqoii::transcode::transcode(&qoi, QoiChannels::Rgb).unwrap();
+    assert!(summary.alpha_dropped);
+    assert_eq!(summary.pixels_changed, 2);
+
+    let (header, mut decoder) = QoiDecoder::new(rgb.iter().copied()).unwrap();
+    assert_eq!(header.channels, QoiChannels::Rgb);
+    assert!(decoder.all(|px| px.a == 255));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn qoi_to_qoi_truncated() {
+    let header = QoiHeader::new(4, 1, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+    let pixels = [
+        Pixel::rgba(10, 20, 30, 255),
+        Pixel::rgba(10, 20, 30, 128),
+        Pixel::rgba(10, 20, 30, 0),
+        Pixel::rgba(40, 50, 60, 255),
+    ];
+    let qoi = QoiEncoder::new(header, pixels.iter().cloned()).collect::<Vec<_>>();
+
+    // drop the footer and the last chunk
+    let truncated = &qoi[..qoi.len() - QOI_FOOTER.len() - 4];
+    assert_eq!(
+        arqoii::transcode::transcode(truncated, QoiChannels::Rgb),
+        Err(DecodeError::PixelCountMismatch {
+            expected: 4,
+            found: 3
+        })
+    );
+}
+
 fn transcode(name: &str, alt_header: Option<QoiHeader>) {
     let reference_qoi = std::fs::read(format!("tests/test-images/{name}.qoi")).unwrap();
 
@@ -77,6 +312,26 @@ fn transcode(name: &str, alt_header: Option<QoiHeader>) {
 
     assert!(Iterator::eq(our_qoi, reference_qoi));
 }
+
+#[test]
+fn push_encoder_matches_reference() {
+    for name in ["dice", "edgecase", "qoi_logo", "testcard_rgba"] {
+        let reference_qoi = std::fs::read(format!("tests/test-images/qoi/{name}.qoi")).unwrap();
+        let header = QoiHeader::from_bytes(reference_qoi[..14].try_into().unwrap()).unwrap();
+
+        let png_bytes = std::fs::read(format!("tests/test-images/qoi/{name}.png")).unwrap();
+        let (_info, png_px) = load_png(&png_bytes);
+
+        let mut push_encoder = PushEncoder::new(header);
+        let mut pushed_qoi = vec![];
+        for px in png_px {
+            pushed_qoi.extend(push_encoder.push(px));
+        }
+        pushed_qoi.extend(push_encoder.finish());
+
+        assert_eq!(pushed_qoi, reference_qoi, "{name}");
+    }
+}
 use png::OutputInfo;
 
 fn load_png(data: &[u8]) -> (OutputInfo, Vec<Pixel>) {
@@ -126,3 +381,203 @@ fn load_png(data: &[u8]) -> (OutputInfo, Vec<Pixel>) {
 
     (info, result)
 }
+
+#[test]
+fn fit_to_header_pixels() {
+    let header = QoiHeader::new(2, 2, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+    let pixels = vec![Pixel::rgb(1, 2, 3); 6];
+
+    let truncated = fit_to_header(pixels.iter().cloned(), &header);
+    assert_eq!(truncated.size_hint(), (4, Some(4)));
+    assert_eq!(truncated.collect::<Vec<_>>(), pixels[..4]);
+
+    let fill = Pixel::rgba(9, 9, 9, 9);
+    let padded = fit_to_header(pixels[..1].iter().cloned(), &header)
+        .fill(fill)
+        .collect::<Vec<_>>();
+    assert_eq!(padded, [pixels[0], fill, fill, fill]);
+
+    let bytes = QoiEncoder::new(header, fit_to_header(pixels, &header)).collect::<Vec<_>>();
+    let (decoded_header, decoder) = QoiDecoder::new(bytes.into_iter()).unwrap();
+    assert_eq!(decoded_header, header);
+    assert_eq!(decoder.count(), 4);
+}
+
+#[test]
+fn fallible_pixels() {
+    let header = QoiHeader::new(3, 1, QoiChannels::Rgba, QoiColorSpace::SRgbWithLinearAlpha);
+    let pixels = [
+        Pixel::rgb(1, 2, 3),
+        Pixel::rgb(1, 2, 3),
+        Pixel::rgba(4, 5, 6, 7),
+    ];
+
+    let qoi = TryQoiEncoder::new(header, pixels.iter().cloned().map(Ok::<_, &str>))
+        .collect::<Result<Vec<_>, _>>();
+    assert_eq!(
+        qoi,
+        Ok(QoiEncoder::new(header, pixels.iter().cloned()).collect())
+    );
+
+    let failing = pixels
+        .iter()
+        .cloned()
+        .map(Ok)
+        .take(2)
+        .chain([Err("broken source"), Ok(Pixel::rgb(0, 0, 0))]);
+    let mut encoder = TryQoiEncoder::new(header, failing);
+    let bytes = encoder.by_ref().map_while(Result::ok).collect::<Vec<_>>();
+    // the header and the Luma Chunk of the first pixel, the pending run is dropped
+    assert_eq!(bytes.len(), 14 + 2);
+    assert_eq!(
+        TryQoiEncoder::new(header, [Err::<Pixel, _>("broken source")].into_iter())
+            .collect::<Result<Vec<_>, _>>(),
+        Err("broken source")
+    );
+    assert_eq!(encoder.next(), None);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn slice() {
+    for image in [
+        "qoi/dice",
+        "qoi/kodim10",
+        "qoi/qoi_logo",
+        "qoi/testcard_rgba",
+    ] {
+        let reference_qoi = std::fs::read(format!("tests/test-images/{image}.qoi")).unwrap();
+        let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+        let pixels = decoder.collect::<Vec<_>>();
+
+        assert_eq!(
+            encode_slice(header, &pixels),
+            QoiEncoder::new(header, pixels.iter().copied()).collect::<Vec<_>>(),
+            "{image}"
+        );
+    }
+
+    // runs starting with the initial previous pixel and spanning multiple run chunks
+    let mut pixels = vec![Pixel::rgb(0, 0, 0); 130];
+    pixels.extend([Pixel::rgb(1, 1, 1); 124]);
+    pixels.push(Pixel::rgba(0, 0, 0, 0));
+    let header = QoiHeader::new(
+        pixels.len() as u32,
+        1,
+        QoiChannels::Rgba,
+        QoiColorSpace::SRgbWithLinearAlpha,
+    );
+    assert_eq!(
+        encode_slice(header, &pixels),
+        QoiEncoder::new(header, pixels.iter().copied()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn bgra_bytes() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/testcard_rgba.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+    let bgra = pixels
+        .iter()
+        .flat_map(|px| [px.b, px.g, px.r, px.a])
+        .collect::<Vec<_>>();
+
+    let qoi = encode_bgra_bytes(header, &bgra);
+    assert_eq!(qoi, encode_to_vec(header, pixels));
+    assert_eq!(decode_to_bgra_bytes(&qoi), Ok((header, bgra)));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn write_to() {
+    /// A writer accepting at most `capacity` bytes, failing afterwards like a closed pipe
+    struct Pipe {
+        data: Vec<u8>,
+        capacity: usize,
+    }
+
+    impl std::io::Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.capacity - self.data.len());
+            if len == 0 {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            self.data.extend(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let reference_qoi = std::fs::read("tests/test-images/qoi/kodim10.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+    let expected = encode_to_vec(header, pixels.iter().copied());
+
+    let mut out = Vec::new();
+    let written = QoiEncoder::new(header, pixels.iter().copied())
+        .write_to(&mut out)
+        .unwrap();
+    assert_eq!(written, expected.len());
+    assert_eq!(out, expected);
+
+    let mut pipe = Pipe {
+        data: Vec::new(),
+        capacity: 10_000,
+    };
+    let error = QoiEncoder::new(header, pixels.iter().copied())
+        .write_to(&mut pipe)
+        .unwrap_err();
+    assert_eq!(error.written, 10_000);
+    assert_eq!(error.error.kind(), std::io::ErrorKind::BrokenPipe);
+    assert_eq!(pipe.data, expected[..10_000]);
+}
+
+#[test]
+fn split_header() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let (header, decoder) = QoiDecoder::new(reference_qoi.iter().copied()).unwrap();
+    let pixels = decoder.collect::<Vec<_>>();
+
+    let (header_bytes, data) = encode_split(header, pixels.iter().copied());
+    assert_eq!(header_bytes, header.to_bytes());
+
+    let data = data.collect::<Vec<_>>();
+    assert!(data.ends_with(&QOI_FOOTER));
+    assert_eq!(
+        [header_bytes.as_slice(), &data].concat(),
+        QoiEncoder::new(header, pixels.into_iter()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn autodetect_channels() {
+    let opaque = vec![Pixel::rgb(1, 2, 3); 4];
+    let (header, encoder) =
+        QoiEncoder::new_autodetect(2, 2, QoiColorSpace::AllChannelsLinear, &opaque);
+    assert_eq!(
+        header,
+        QoiHeader::new(2, 2, QoiChannels::Rgb, QoiColorSpace::AllChannelsLinear)
+    );
+    assert_eq!(
+        encoder.collect::<Vec<_>>(),
+        QoiEncoder::new(header, opaque.iter().copied()).collect::<Vec<_>>()
+    );
+
+    let mut transparent = opaque.clone();
+    transparent[3].a = 0;
+    let (header, _) =
+        QoiEncoder::new_autodetect(2, 2, QoiColorSpace::AllChannelsLinear, &transparent);
+    assert_eq!(header.channels, QoiChannels::Rgba);
+}
+
+#[test]
+fn reencode() {
+    let reference_qoi = std::fs::read("tests/test-images/qoi/dice.qoi").unwrap();
+    let (_header, decoder) = QoiDecoder::from_slice(&reference_qoi).unwrap();
+    assert_eq!(decoder.reencode().collect::<Vec<_>>(), reference_qoi);
+}